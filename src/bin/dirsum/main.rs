@@ -0,0 +1,800 @@
+use chrono::prelude::*;
+use clap::{Parser, ValueEnum};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{self, Debug};
+use std::fs::{self, DirEntry, Metadata};
+use std::io::{self, IsTerminal, Write};
+use std::iter::Peekable;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::Chars;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tabled::settings::{location::ByColumnName, Disable, Style};
+use tabled::{Table, Tabled};
+
+mod tree;
+mod tui;
+
+use tree::{write_tree, Node, TreeStyle};
+
+#[derive(Parser, Debug)]
+#[command(name = "dirsum", version = "0.1.0", about = "A simple to use directory summarizer", long_about = None)]
+struct Cli {
+    /// Path to directory to summarize
+    #[arg(value_name = "DIRECTORY")]
+    path: PathBuf,
+    /// Output format. Machine-readable formats leave out the banner and totals, use exact byte sizes and
+    /// ISO 8601 timestamps, and always include every time
+    #[arg(value_enum, long, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    /// Size format to use for file and directory sizes
+    #[arg(value_enum, long, value_name = "SIZE UNIT")]
+    size: Option<FileSizeFormat>,
+    /// With `--size auto`, use SI units (kB, MB, GB, powers of 1000) instead of IEC units (KiB, MiB, GiB,
+    /// powers of 1024)
+    #[arg(long)]
+    si: bool,
+    /// Order of the rows. Sizes and times are sorted largest and newest first, names in natural order so
+    /// `file2` comes before `file10`
+    #[arg(value_enum, long, default_value_t = SortKey::Name)]
+    sort: SortKey,
+    /// Reverse the sort order
+    #[arg(short, long)]
+    reverse: bool,
+    /// List directories before files, whatever the sort order
+    #[arg(long)]
+    dirs_first: bool,
+    /// Show the whole hierarchy as a tree, with each entry's size, file count and share of its parent,
+    /// largest first
+    #[arg(long, conflicts_with_all = ["format", "sort"])]
+    tree: bool,
+    /// With `--tree`, only show this many levels below the directory. Sizes still include everything
+    #[arg(long, value_name = "N", requires = "tree")]
+    depth: Option<usize>,
+    /// Browse the directory in a terminal interface like ncdu, with bar graphs of sizes, switching between
+    /// apparent size and disk usage, and deleting marked entries after confirmation
+    #[arg(long, conflicts_with_all = ["format", "tree"])]
+    interactive: bool,
+    /// Number of threads scanning directories concurrently, the number of CPUs by default. The output is
+    /// the same whatever the number
+    #[arg(long, value_name = "N")]
+    threads: Option<NonZeroUsize>,
+    /// Don't report progress on stderr during long scans. Progress is only ever shown when stderr is a
+    /// terminal
+    #[arg(long)]
+    no_progress: bool,
+    /// Abort on the first entry that can't be read instead of skipping it with a warning. Symlinks aren't
+    /// followed, so dangling ones are listed like any other entry
+    #[arg(long)]
+    strict: bool,
+    /// Show when each entry was last accessed
+    #[arg(long)]
+    accessed: bool,
+    /// Show when each entry's metadata last changed (ctime)
+    #[arg(long)]
+    changed: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Hash)]
+enum FileSizeFormat {
+    /// "Bytes"
+    B,
+    /// "Kilobytes", 1000 bytes
+    KB,
+    /// "Megabytes", 1000 kilobytes
+    MB,
+    /// "Gigabytes", 1000 megabytes
+    GB,
+    /// "Kibibytes", 1024 bytes
+    #[value(name = "kib")]
+    KiB,
+    /// "Mebibytes", 1024 kibibytes
+    #[value(name = "mib")]
+    MiB,
+    /// "Gibibytes", 1024 mebibytes
+    #[value(name = "gib")]
+    GiB,
+    /// The most readable unit for each size, like `ls -h`
+    Auto,
+}
+
+/// How to print the rows
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum OutputFormat {
+    /// A table for reading in a terminal
+    Table,
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// CSV with a header row, with paths written as raw bytes
+    Csv,
+    /// A YAML sequence of mappings
+    Yaml,
+}
+
+/// Column to sort rows by
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum SortKey {
+    Name,
+    Size,
+    Type,
+    Created,
+    Modified,
+    Extension,
+}
+
+/// A size in bytes, displayed in the unit chosen on the command line
+#[derive(Debug, Clone, Copy)]
+struct FileSize {
+    bytes: u64,
+    format: FileSizeFormat,
+    si: bool,
+}
+
+/// Serialized as the exact number of bytes, whatever the display unit
+impl Serialize for FileSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.bytes)
+    }
+}
+
+impl fmt::Display for FileSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.format != FileSizeFormat::Auto {
+            return match get_file_size_divisor(self.format) {
+                1 => write!(f, "{}", self.bytes),
+                divisor => write!(f, "{:.2}", self.bytes as f64 / divisor as f64),
+            };
+        }
+
+        let (base, units): (f64, [&str; 6]) = match self.si {
+            true => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB"]),
+            false => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        };
+        let mut value: f64 = self.bytes as f64;
+        let mut unit: usize = 0;
+        while value >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        match unit {
+            0 => write!(f, "{} {}", self.bytes, units[0]),
+            _ => write!(f, "{:.1} {}", value, units[unit]),
+        }
+    }
+}
+
+#[derive(Debug, Tabled, Serialize)]
+struct FileAttribs {
+    /// Name for display, with bytes that aren't valid UTF-8 escaped
+    #[serde(skip)]
+    filename: String,
+    /// Exact path, for machine-readable output
+    #[tabled(skip)]
+    #[serde(flatten)]
+    path: RawPath,
+    file_type: String,
+    size: FileSize,
+    file_count: u64,
+    creation_time: FileTime,
+    modified_time: FileTime,
+    accessed_time: FileTime,
+    changed_time: FileTime,
+}
+
+/// A path serialized as `path`, plus its exact bytes as `path_bytes` when it isn't valid UTF-8, since
+/// JSON and YAML strings can only hold Unicode
+#[derive(Debug)]
+struct RawPath(PathBuf);
+
+impl Serialize for RawPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match self.0.to_str() {
+            Some(path) => map.serialize_entry("path", path)?,
+            None => {
+                map.serialize_entry("path", &escape_filename(&self.0))?;
+                map.serialize_entry("path_bytes", &get_path_bytes(&self.0))?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// A timestamp in local time with second precision, or none when the platform or filesystem doesn't
+/// record it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct FileTime(Option<DateTime<Local>>);
+
+/// Serialized as an ISO 8601 timestamp, or null when missing
+impl Serialize for FileTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl fmt::Display for FileTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(time) => write!(f, "{}", time),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// An entry that couldn't be read, along with the reason
+#[derive(Debug)]
+struct EntryError {
+    path: PathBuf,
+    error: io::Error,
+}
+
+/// How long a scan runs before its progress is reported
+const PROGRESS_DELAY: Duration = Duration::from_secs(1);
+
+/// Time between progress updates
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// State shared by the threads of a scan: errors collected so one unreadable entry doesn't stop the whole
+/// report, the threads still available and the counts for progress reporting
+struct Scan {
+    strict: bool,
+    errors: Mutex<Vec<EntryError>>,
+    /// Threads that can still be started to scan subdirectories, beyond the ones already running
+    spare_threads: AtomicUsize,
+    files: AtomicU64,
+    directories: AtomicU64,
+    /// Set when progress is reported to a terminal
+    progress: Option<Mutex<Progress>>,
+}
+
+/// When the scan started and when its progress was last printed
+struct Progress {
+    started: Instant,
+    printed: Option<Instant>,
+}
+
+impl Scan {
+    /// A scan using up to `threads` threads, reporting progress on stderr when `progress` is set
+    fn new(strict: bool, threads: usize, progress: bool) -> Scan {
+        Scan {
+            strict,
+            errors: Mutex::new(Vec::new()),
+            spare_threads: AtomicUsize::new(threads.saturating_sub(1)),
+            files: AtomicU64::new(0),
+            directories: AtomicU64::new(0),
+            progress: progress.then(|| {
+                Mutex::new(Progress {
+                    started: Instant::now(),
+                    printed: None,
+                })
+            }),
+        }
+    }
+
+    /// Record a skipped entry, or return the error in strict mode so the scan is aborted
+    fn record(&self, error: EntryError) -> Result<(), EntryError> {
+        match self.strict {
+            true => Err(error),
+            false => {
+                self.errors
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(error);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reserve a thread for scanning a subdirectory, returning false if all of them are busy
+    fn reserve_thread(&self) -> bool {
+        self.spare_threads
+            .fetch_update(AtomicOrdering::AcqRel, AtomicOrdering::Acquire, |spare| {
+                spare.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    /// Give back a thread reserved with `reserve_thread`
+    fn release_thread(&self) {
+        self.spare_threads.fetch_add(1, AtomicOrdering::AcqRel);
+    }
+
+    /// Count a scanned directory and its files, and print the totals so far if the scan has been running
+    /// for a while. Printing is skipped when another thread is already doing it.
+    fn add_directory(&self, files: u64) {
+        let files: u64 = self.files.fetch_add(files, AtomicOrdering::Relaxed) + files;
+        let directories: u64 = self.directories.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+
+        let mut progress = match self.progress.as_ref().map(Mutex::try_lock) {
+            Some(Ok(progress)) => progress,
+            _ => return,
+        };
+        let now: Instant = Instant::now();
+        if now.duration_since(progress.started) < PROGRESS_DELAY
+            || progress
+                .printed
+                .is_some_and(|printed| now.duration_since(printed) < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        progress.printed = Some(now);
+        eprint!(
+            "\r\x1b[KScanned {} files in {} directories",
+            files, directories
+        );
+    }
+
+    /// Clear the progress line, if one was printed, before the results are written
+    fn finish_progress(&self) {
+        let progress = match &self.progress {
+            Some(progress) => progress.lock().unwrap_or_else(PoisonError::into_inner),
+            None => return,
+        };
+        if progress.printed.is_some() {
+            eprint!("\r\x1b[K");
+        }
+    }
+
+    /// The skipped entries, sorted by path so they're listed the same way whatever the thread count
+    fn into_errors(self) -> Vec<EntryError> {
+        let mut errors: Vec<EntryError> = self
+            .errors
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        errors
+    }
+}
+
+/// Main function to summarize a directory and its members. Exits with a failure status when any entry
+/// had to be skipped.
+fn main() -> ExitCode {
+    let args: Cli = Cli::parse();
+    let path: &Path = Path::new(&args.path);
+
+    let mut file_format_map: HashMap<FileSizeFormat, String> = HashMap::new();
+    file_format_map.insert(FileSizeFormat::B, "Bytes".to_string());
+    file_format_map.insert(FileSizeFormat::KB, "Kilobytes".to_string());
+    file_format_map.insert(FileSizeFormat::MB, "Megabytes".to_string());
+    file_format_map.insert(FileSizeFormat::GB, "Gigabytes".to_string());
+    file_format_map.insert(FileSizeFormat::KiB, "Kibibytes".to_string());
+    file_format_map.insert(FileSizeFormat::MiB, "Mebibytes".to_string());
+    file_format_map.insert(FileSizeFormat::GiB, "Gibibytes".to_string());
+    let auto_label: &str = match args.si {
+        true => "Automatic (SI units)",
+        false => "Automatic (IEC units)",
+    };
+    file_format_map.insert(FileSizeFormat::Auto, auto_label.to_string());
+
+    if args.format == OutputFormat::Table && !args.interactive {
+        println!("\nDirectory to summarize: {}", path.display());
+        print!("Size format: ");
+        match args.size {
+            Some(size) => println!("{}\n", file_format_map.get(&size).unwrap()),
+            None => println!("Bytes\n"),
+        }
+    }
+
+    let size_format: FileSizeFormat = args.size.unwrap_or(FileSizeFormat::B);
+    let threads: usize = match args.threads {
+        Some(threads) => threads.get(),
+        None => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+    };
+    let scan: Scan = Scan::new(
+        args.strict,
+        threads,
+        !args.no_progress && io::stderr().is_terminal(),
+    );
+
+    // Every mode works from one scan of the whole directory, so flat subdirectories are scanned in parallel
+    let scanned: Result<Node, EntryError> = Node::scan_dir(path, &scan);
+    let mut root: Node = match scanned {
+        Ok(root) => root,
+        Err(error) => {
+            scan.finish_progress();
+            eprintln!("Problem reading {}: {}", error.path.display(), error.error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.tree || args.interactive {
+        scan.finish_progress();
+        if args.interactive {
+            if let Err(error) = tui::run(root, size_format, args.si) {
+                eprintln!("Problem running the interface: {}", error);
+                return ExitCode::FAILURE;
+            }
+            return report_errors(scan);
+        }
+
+        root.sort(args.reverse, args.dirs_first);
+        let style: TreeStyle = TreeStyle {
+            size_format,
+            si: args.si,
+            depth: args.depth,
+        };
+        if let Err(error) = write_tree(&root, &style, io::stdout().lock()) {
+            eprintln!("Problem writing output: {}", error);
+            return ExitCode::FAILURE;
+        }
+        return report_errors(scan);
+    }
+
+    // Vector that will hold file attributes structs
+    let mut file_attribs_vec: Vec<FileAttribs> = Vec::new();
+
+    for child in &root.children {
+        // Get attributes for each top-level entry as struct and add to vector, skipping entries that fail
+        let file_attribs: Result<FileAttribs, EntryError> = normalize_path(&child.path)
+            .map_err(|error| EntryError {
+                path: child.path.clone(),
+                error,
+            })
+            .and_then(|file_path| get_all_file_attributes(child, &file_path, size_format, args.si));
+
+        match file_attribs {
+            Ok(file_attribs) => file_attribs_vec.push(file_attribs),
+            Err(error) => {
+                if let Err(error) = scan.record(error) {
+                    scan.finish_progress();
+                    eprintln!("Problem reading {}: {}", error.path.display(), error.error);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+
+    scan.finish_progress();
+
+    sort_file_attribs(
+        &mut file_attribs_vec,
+        args.sort,
+        args.reverse,
+        args.dirs_first,
+    );
+
+    if args.format == OutputFormat::Table {
+        // Creation of table from vector of structs
+        let mut table: Table = Table::new(&file_attribs_vec);
+        table.with(Style::psql());
+        if !args.accessed {
+            table.with(Disable::column(ByColumnName::new("accessed_time")));
+        }
+        if !args.changed {
+            table.with(Disable::column(ByColumnName::new("changed_time")));
+        }
+        println!("{}", table);
+
+        // Total number of items within the directory
+        println!("\nTotal items: {}", root.children.len());
+    } else if let Err(error) =
+        write_file_attribs(args.format, &file_attribs_vec, io::stdout().lock())
+    {
+        eprintln!("Problem writing output: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    report_errors(scan)
+}
+
+/// Print the entries that were skipped anywhere below the directory as warnings, and return a failure
+/// status if there were any
+fn report_errors(scan: Scan) -> ExitCode {
+    let errors: Vec<EntryError> = scan.into_errors();
+    if errors.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    eprintln!("\nWarnings: {} entries skipped", errors.len());
+    for EntryError { path, error } in &errors {
+        eprintln!("  {}: {}", path.display(), error);
+    }
+    ExitCode::FAILURE
+}
+
+/// Write rows in one of the machine-readable formats
+fn write_file_attribs(
+    format: OutputFormat,
+    file_attribs_vec: &[FileAttribs],
+    mut writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Table => unreachable!("tables are printed with tabled"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, file_attribs_vec)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Ndjson => {
+            for file_attribs in file_attribs_vec {
+                serde_json::to_writer(&mut writer, file_attribs)?;
+                writeln!(writer)?;
+            }
+        }
+        OutputFormat::Yaml => serde_yaml::to_writer(&mut writer, file_attribs_vec)?,
+        OutputFormat::Csv => {
+            // Written field by field so paths keep their exact bytes
+            let mut csv_writer = csv::Writer::from_writer(&mut writer);
+            csv_writer.write_record([
+                "path",
+                "file_type",
+                "size",
+                "file_count",
+                "creation_time",
+                "modified_time",
+                "accessed_time",
+                "changed_time",
+            ])?;
+            for file_attribs in file_attribs_vec {
+                let times: Vec<String> = [
+                    file_attribs.creation_time,
+                    file_attribs.modified_time,
+                    file_attribs.accessed_time,
+                    file_attribs.changed_time,
+                ]
+                .iter()
+                .map(|time| {
+                    time.0
+                        .map(|time| time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+                        .unwrap_or_default()
+                })
+                .collect();
+                let mut record: Vec<Vec<u8>> = vec![
+                    get_path_bytes(&file_attribs.path.0),
+                    file_attribs.file_type.clone().into_bytes(),
+                    file_attribs.size.bytes.to_string().into_bytes(),
+                    file_attribs.file_count.to_string().into_bytes(),
+                ];
+                record.extend(times.into_iter().map(String::into_bytes));
+                csv_writer.write_record(&record)?;
+            }
+            csv_writer.flush()?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Sort rows by a column, breaking ties by name. With `dirs_first`, directories stay ahead of files even
+/// when the order is reversed.
+fn sort_file_attribs(
+    file_attribs_vec: &mut [FileAttribs],
+    sort: SortKey,
+    reverse: bool,
+    dirs_first: bool,
+) {
+    file_attribs_vec.sort_by(|a, b| {
+        let by_name = || compare_natural(&a.filename, &b.filename);
+        let ordering: Ordering = match sort {
+            SortKey::Name => by_name(),
+            SortKey::Size => b.size.bytes.cmp(&a.size.bytes).then_with(by_name),
+            SortKey::Type => a.file_type.cmp(&b.file_type).then_with(by_name),
+            SortKey::Created => b.creation_time.cmp(&a.creation_time).then_with(by_name),
+            SortKey::Modified => b.modified_time.cmp(&a.modified_time).then_with(by_name),
+            SortKey::Extension => get_extension(&a.filename)
+                .cmp(&get_extension(&b.filename))
+                .then_with(by_name),
+        };
+        match reverse {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    });
+    if dirs_first {
+        // Stable, so the order within directories and files is kept
+        file_attribs_vec.sort_by_key(|file_attribs| file_attribs.file_type != "Directory");
+    }
+}
+
+/// Get the lowercase extension of a file name, empty when there is none
+fn get_extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Compare names in natural order: runs of digits are compared by their numeric value and everything
+/// else case-insensitively, falling back to a plain comparison for names that only differ in case or
+/// leading zeros
+fn compare_natural(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        let (a_char, b_char): (char, char) = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&a_char), Some(&b_char)) => (a_char, b_char),
+        };
+
+        let ordering: Ordering = if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let a_digits: String = take_digits(&mut a_chars);
+            let b_digits: String = take_digits(&mut b_chars);
+            let a_number: &str = a_digits.trim_start_matches('0');
+            let b_number: &str = b_digits.trim_start_matches('0');
+            a_number
+                .len()
+                .cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number))
+        } else {
+            a_chars.next();
+            b_chars.next();
+            a_char.to_lowercase().cmp(b_char.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Consume a run of ASCII digits
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits: String = String::new();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+/// Get all file attributes for a scanned entry and return a Result with a FileAttribs struct. Sizes come
+/// from the scan, so directories are sized by everything below them, and symlinks are described by
+/// themselves rather than their target, as in the tree
+fn get_all_file_attributes(
+    node: &Node,
+    file_path: &Path,
+    size_format: FileSizeFormat,
+    si: bool,
+) -> Result<FileAttribs, EntryError> {
+    let metadata: Metadata = fs::symlink_metadata(&node.path).map_err(|error| EntryError {
+        path: node.path.clone(),
+        error,
+    })?;
+
+    let file_type: &str = match (node.is_dir, node.is_symlink) {
+        (true, _) => "Directory",
+        (false, true) => "Symlink",
+        (false, false) => "File",
+    };
+
+    // Times the platform or filesystem doesn't record are shown as `-`
+    let file_attribs = FileAttribs {
+        filename: escape_filename(file_path),
+        path: RawPath(file_path.to_path_buf()),
+        file_type: file_type.to_string(),
+        size: FileSize {
+            bytes: node.size,
+            format: size_format,
+            si,
+        },
+        file_count: node.file_count,
+        creation_time: get_file_time(metadata.created().ok()),
+        modified_time: get_file_time(metadata.modified().ok()),
+        accessed_time: get_file_time(metadata.accessed().ok()),
+        changed_time: get_file_time(get_changed_time(&metadata)),
+    };
+
+    Ok(file_attribs)
+}
+
+/// Convert a timestamp to local time with second precision
+fn get_file_time(time: Option<SystemTime>) -> FileTime {
+    let seconds: i64 = match time.map(|time| time.duration_since(SystemTime::UNIX_EPOCH)) {
+        Some(Ok(since_epoch)) => since_epoch.as_secs() as i64,
+        // Before the epoch
+        Some(Err(error)) => -(error.duration().as_secs() as i64),
+        None => return FileTime(None),
+    };
+    FileTime(Local.timestamp_opt(seconds, 0).single())
+}
+
+/// Get the time the metadata of an entry last changed (ctime), which is only recorded on Unix
+#[cfg(unix)]
+fn get_changed_time(metadata: &Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+
+    let since_epoch = Duration::new(metadata.ctime().unsigned_abs(), 0);
+    match metadata.ctime() >= 0 {
+        true => SystemTime::UNIX_EPOCH.checked_add(since_epoch),
+        false => SystemTime::UNIX_EPOCH.checked_sub(since_epoch),
+    }
+}
+
+/// Get the time the metadata of an entry last changed (ctime), which is only recorded on Unix
+#[cfg(not(unix))]
+fn get_changed_time(_metadata: &Metadata) -> Option<SystemTime> {
+    None
+}
+
+/// Get directory members for a given path and return a Result with a vector of DirEntry
+fn get_dir_members(path: &Path) -> io::Result<Vec<DirEntry>> {
+    let mut members: Vec<DirEntry> = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry: DirEntry = entry?;
+        members.push(entry);
+    }
+    Ok(members)
+}
+
+/// Escape a path for display: bytes that aren't valid UTF-8 become `\xNN` and control characters such as
+/// newlines use their Rust escapes, so any name fits on one table row and distinct names stay distinct
+#[cfg(unix)]
+fn escape_filename(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut escaped: String = String::new();
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c.is_control() || c == '\\' {
+                true => escaped.extend(c.escape_default()),
+                false => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+/// Escape a path for display, replacing anything that isn't valid Unicode
+#[cfg(not(unix))]
+fn escape_filename(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Get the exact bytes of a path
+#[cfg(unix)]
+fn get_path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+/// Get the bytes of a path as UTF-8, replacing anything that isn't valid Unicode
+#[cfg(not(unix))]
+fn get_path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+/// Normalize a path and return a Result with a PathBuf
+fn normalize_path(path: &Path) -> io::Result<PathBuf> {
+    // Get the components of the path and convert them to OsStr and collect them into a vector
+    let path_components: Vec<_> = path.components().map(|c| c.as_os_str()).collect();
+
+    // Join the components back into a path, without doubling the separator after the root
+    let mut normalized_path: OsString = OsString::new();
+    for component in path_components {
+        if !normalized_path.is_empty() && !normalized_path.to_string_lossy().ends_with('/') {
+            normalized_path.push("/");
+        }
+        normalized_path.push(component);
+    }
+    Ok(PathBuf::from(normalized_path))
+}
+
+/// Get the number of bytes in one unit of a fixed size format
+fn get_file_size_divisor(size_format: FileSizeFormat) -> u64 {
+    match size_format {
+        FileSizeFormat::B | FileSizeFormat::Auto => 1,
+        FileSizeFormat::KB => 1000,
+        FileSizeFormat::MB => 1000 * 1000,
+        FileSizeFormat::GB => 1000 * 1000 * 1000,
+        FileSizeFormat::KiB => 1024,
+        FileSizeFormat::MiB => 1024 * 1024,
+        FileSizeFormat::GiB => 1024 * 1024 * 1024,
+    }
+}
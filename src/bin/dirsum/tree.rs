@@ -0,0 +1,356 @@
+//! Recursive model of a directory's contents with aggregated sizes, and its rendering for `--tree`.
+
+use std::cmp::Ordering;
+use std::fs::{self, DirEntry, Metadata};
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::thread::{self, ScopedJoinHandle};
+
+use crate::{
+    compare_natural, escape_filename, get_dir_members, EntryError, FileSize, FileSizeFormat, Scan,
+};
+
+/// A file or directory, with the total size and file count of everything below it
+#[derive(Debug)]
+pub struct Node {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Symlinks aren't followed, so they're sized by the link itself
+    pub is_symlink: bool,
+    /// Size in bytes, the sum of all files below for directories
+    pub size: u64,
+    /// Space allocated on disk in bytes, which can differ from the size for sparse and small files
+    pub disk_size: u64,
+    /// Number of files, 1 for a file and 0 for a symlink
+    pub file_count: u64,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// Scan a directory recursively and return a Result with its tree. Symlinks are counted by their own
+    /// size and not followed. Entries below the directory that can't be read are recorded in the scan and
+    /// left out of the tree. Subdirectories are scanned on other threads while the scan has spare ones,
+    /// and children are sorted by path so the tree is the same whatever the thread count.
+    pub fn scan_dir(path: &Path, scan: &Scan) -> Result<Node, EntryError> {
+        let members: Vec<DirEntry> = get_dir_members(path).map_err(|error| EntryError {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let mut children: Vec<Node> = Vec::new();
+        let mut subdirectories: Vec<PathBuf> = Vec::new();
+        for member in members {
+            let metadata: Metadata = match fs::symlink_metadata(member.path()) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    scan.record(EntryError {
+                        path: member.path(),
+                        error,
+                    })?;
+                    continue;
+                }
+            };
+            if metadata.is_dir() {
+                subdirectories.push(member.path());
+            } else {
+                children.push(Node {
+                    path: member.path(),
+                    is_dir: false,
+                    is_symlink: metadata.is_symlink(),
+                    size: metadata.len(),
+                    disk_size: get_disk_size(&metadata),
+                    file_count: match metadata.is_symlink() {
+                        true => 0,
+                        false => 1,
+                    },
+                    children: Vec::new(),
+                });
+            }
+        }
+        scan.add_directory(children.len() as u64);
+
+        let scanned: Vec<Result<Node, EntryError>> = thread::scope(|scope| {
+            let mut spawned: Vec<ScopedJoinHandle<Result<Node, EntryError>>> = Vec::new();
+            let mut scanned: Vec<Result<Node, EntryError>> = Vec::new();
+            for subdirectory in &subdirectories {
+                match scan.reserve_thread() {
+                    true => spawned.push(scope.spawn(move || {
+                        let result: Result<Node, EntryError> = Node::scan_dir(subdirectory, scan);
+                        scan.release_thread();
+                        result
+                    })),
+                    false => scanned.push(Node::scan_dir(subdirectory, scan)),
+                }
+            }
+            for handle in spawned {
+                scanned.push(
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| panic::resume_unwind(panic)),
+                );
+            }
+            scanned
+        });
+        for result in scanned {
+            match result {
+                Ok(child) => children.push(child),
+                Err(error) => scan.record(error)?,
+            }
+        }
+        children.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut node: Node = Node {
+            path: path.to_path_buf(),
+            is_dir: true,
+            is_symlink: false,
+            size: 0,
+            disk_size: 0,
+            file_count: 0,
+            children,
+        };
+        node.update_totals();
+        Ok(node)
+    }
+
+    /// Recompute a directory's totals from its children
+    fn update_totals(&mut self) {
+        self.size = self.children.iter().map(|child| child.size).sum();
+        self.disk_size = self.children.iter().map(|child| child.disk_size).sum();
+        self.file_count = self.children.iter().map(|child| child.file_count).sum();
+    }
+
+    /// Find the node for a path at or below this one
+    pub fn find(&self, path: &Path) -> Option<&Node> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find(|child| path.starts_with(&child.path))
+            .and_then(|child| child.find(path))
+    }
+
+    /// Remove the node for a path below this one, updating the totals of its ancestors, and return
+    /// whether it was found
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let removed: bool = match self.children.iter().position(|child| child.path == path) {
+            Some(index) => {
+                self.children.remove(index);
+                true
+            }
+            None => self
+                .children
+                .iter_mut()
+                .find(|child| path.starts_with(&child.path))
+                .is_some_and(|child| child.remove(path)),
+        };
+        if removed {
+            self.update_totals();
+        }
+        removed
+    }
+
+    /// Sort the children at every level largest first, breaking ties by name. With `dirs_first`,
+    /// directories come before files.
+    pub fn sort(&mut self, reverse: bool, dirs_first: bool) {
+        self.children.sort_by(|a, b| {
+            let ordering: Ordering = b
+                .size
+                .cmp(&a.size)
+                .then_with(|| compare_natural(&a.name(), &b.name()));
+            match reverse {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+        if dirs_first {
+            self.children.sort_by_key(|child| !child.is_dir);
+        }
+        for child in &mut self.children {
+            child.sort(reverse, dirs_first);
+        }
+    }
+
+    /// The last component of the path, escaped for display
+    pub fn name(&self) -> String {
+        match self.path.file_name() {
+            Some(name) => escape_filename(Path::new(name)),
+            None => escape_filename(&self.path),
+        }
+    }
+}
+
+/// Get the space a file takes up on disk, from its allocated blocks on Unix
+#[cfg(unix)]
+fn get_disk_size(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    // Always counted in 512-byte units, whatever the filesystem's block size
+    metadata.blocks() * 512
+}
+
+/// Get the space a file takes up on disk, which is only known on Unix, so the size is used instead
+#[cfg(not(unix))]
+fn get_disk_size(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Size display options for rendering a tree
+pub struct TreeStyle {
+    pub size_format: FileSizeFormat,
+    pub si: bool,
+    /// Levels of children to show below the root, all of them when `None`
+    pub depth: Option<usize>,
+}
+
+/// Render a tree with box-drawing characters, annotating every node with its size, file count and
+/// percentage of its parent's size
+pub fn write_tree(root: &Node, style: &TreeStyle, mut writer: impl Write) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{} ({})",
+        escape_filename(&root.path),
+        describe(root, None, style)
+    )?;
+    write_children(root, "", 1, style, &mut writer)?;
+    writer.flush()
+}
+
+fn write_children(
+    parent: &Node,
+    prefix: &str,
+    level: usize,
+    style: &TreeStyle,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    if style.depth.is_some_and(|depth| level > depth) {
+        return Ok(());
+    }
+    for (index, child) in parent.children.iter().enumerate() {
+        let last: bool = index + 1 == parent.children.len();
+        let (branch, indent): (&str, &str) = match last {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+        let name: String = match child.is_dir {
+            true => format!("{}/", child.name()),
+            false => child.name(),
+        };
+        writeln!(
+            writer,
+            "{}{}{} ({})",
+            prefix,
+            branch,
+            name,
+            describe(child, Some(parent), style)
+        )?;
+        write_children(
+            child,
+            &format!("{}{}", prefix, indent),
+            level + 1,
+            style,
+            writer,
+        )?;
+    }
+    Ok(())
+}
+
+/// Size, file count for directories, and share of the parent's size
+fn describe(node: &Node, parent: Option<&Node>, style: &TreeStyle) -> String {
+    let mut parts: Vec<String> = vec![FileSize {
+        bytes: node.size,
+        format: style.size_format,
+        si: style.si,
+    }
+    .to_string()];
+    if node.is_dir {
+        parts.push(match node.file_count {
+            1 => "1 file".to_string(),
+            count => format!("{} files", count),
+        });
+    }
+    if let Some(parent) = parent {
+        let percentage: f64 = match parent.size {
+            0 => 0.0,
+            parent_size => node.size as f64 * 100.0 / parent_size as f64,
+        };
+        parts.push(format!("{:.1}%", percentage));
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64) -> Node {
+        Node {
+            path: PathBuf::from(path),
+            is_dir: false,
+            is_symlink: false,
+            size,
+            disk_size: size,
+            file_count: 1,
+            children: Vec::new(),
+        }
+    }
+
+    fn dir(path: &str, children: Vec<Node>) -> Node {
+        let mut node: Node = Node {
+            path: PathBuf::from(path),
+            is_dir: true,
+            is_symlink: false,
+            size: 0,
+            disk_size: 0,
+            file_count: 0,
+            children,
+        };
+        node.update_totals();
+        node
+    }
+
+    /// `root` holding `a.txt` and `sub`, which holds `b.txt` and `deep/c.txt`
+    fn tree() -> Node {
+        dir(
+            "root",
+            vec![
+                file("root/a.txt", 5),
+                dir(
+                    "root/sub",
+                    vec![
+                        file("root/sub/b.txt", 10),
+                        dir("root/sub/deep", vec![file("root/sub/deep/c.txt", 3)]),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn find_looks_below_the_node() {
+        let root: Node = tree();
+        assert_eq!(root.find(Path::new("root")).map(|node| node.size), Some(18));
+        assert_eq!(
+            root.find(Path::new("root/sub/deep/c.txt"))
+                .map(|node| node.size),
+            Some(3)
+        );
+        assert!(root.find(Path::new("root/sub/missing")).is_none());
+        assert!(root.find(Path::new("elsewhere/a.txt")).is_none());
+    }
+
+    #[test]
+    fn remove_updates_the_totals_of_ancestors() {
+        let mut root: Node = tree();
+        assert!(root.remove(Path::new("root/sub/deep/c.txt")));
+        assert!(root.find(Path::new("root/sub/deep/c.txt")).is_none());
+        let sub: &Node = root.find(Path::new("root/sub")).unwrap();
+        assert_eq!((sub.size, sub.file_count), (10, 1));
+        assert_eq!((root.size, root.disk_size, root.file_count), (15, 15, 2));
+
+        assert!(!root.remove(Path::new("root/sub/deep/c.txt")));
+        assert!(root.remove(Path::new("root/sub")));
+        assert_eq!((root.size, root.file_count), (5, 1));
+    }
+}
@@ -0,0 +1,452 @@
+//! Interactive ncdu-style browser for a scanned directory tree, started with `--interactive`.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::tree::Node;
+use crate::{compare_natural, escape_filename, FileSize, FileSizeFormat};
+
+/// Width of the bar graph next to each entry, in characters
+const BAR_WIDTH: usize = 20;
+
+/// Whether keys browse the tree or answer the deletion prompt
+#[derive(PartialEq)]
+enum Mode {
+    Browse,
+    ConfirmDelete,
+}
+
+struct App {
+    root: Node,
+    /// Directory whose children are listed
+    current: PathBuf,
+    list: ListState,
+    /// Show allocated disk space instead of apparent sizes
+    disk_usage: bool,
+    /// Entries marked for deletion, anywhere in the tree
+    marked: BTreeSet<PathBuf>,
+    /// The selected entry marked by `d` when nothing else was, unmarked again if the deletion is cancelled
+    implicit_mark: Option<PathBuf>,
+    mode: Mode,
+    size_format: FileSizeFormat,
+    si: bool,
+    status: String,
+}
+
+/// Run the browser until the user quits
+pub fn run(root: Node, size_format: FileSizeFormat, si: bool) -> io::Result<()> {
+    let mut app: App = App::new(root, size_format, si);
+    let mut terminal: DefaultTerminal = ratatui::try_init()?;
+    let result: io::Result<()> = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(root: Node, size_format: FileSizeFormat, si: bool) -> App {
+        let mut app = App {
+            current: root.path.clone(),
+            root,
+            list: ListState::default(),
+            disk_usage: false,
+            marked: BTreeSet::new(),
+            implicit_mark: None,
+            mode: Mode::Browse,
+            size_format,
+            si,
+            status: String::new(),
+        };
+        app.list.select(Some(0));
+        app
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key: KeyEvent = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+            if self.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Act on a key press, returning whether to quit
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.mode == Mode::Browse {
+            self.status.clear();
+        }
+
+        match self.mode {
+            Mode::ConfirmDelete => {
+                match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        self.implicit_mark = None;
+                        self.delete();
+                    }
+                    _ => {
+                        if let Some(path) = self.implicit_mark.take() {
+                            self.marked.remove(&path);
+                        }
+                        self.status = "Deletion cancelled".to_string();
+                    }
+                }
+                self.mode = Mode::Browse;
+            }
+            Mode::Browse => match code {
+                KeyCode::Char('q') | KeyCode::Esc => return true,
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(10),
+                KeyCode::PageUp => self.move_selection(-10),
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.enter(),
+                KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.leave(),
+                KeyCode::Char('a') => self.disk_usage = !self.disk_usage,
+                KeyCode::Char(' ') => self.toggle_mark(),
+                KeyCode::Char('d') => {
+                    if self.marked.is_empty() {
+                        self.implicit_mark = self.selected().map(|node| node.path.clone());
+                        self.marked.extend(self.implicit_mark.clone());
+                    }
+                    if !self.marked.is_empty() {
+                        self.mode = Mode::ConfirmDelete;
+                    }
+                }
+                _ => (),
+            },
+        }
+        false
+    }
+
+    /// The directory being listed, falling back to the root if it has gone
+    fn current_node(&self) -> &Node {
+        self.root.find(&self.current).unwrap_or(&self.root)
+    }
+
+    /// Size of a node in the selected measure
+    fn measure(&self, node: &Node) -> u64 {
+        match self.disk_usage {
+            true => node.disk_size,
+            false => node.size,
+        }
+    }
+
+    /// Children of the current directory, largest first
+    fn children(&self) -> Vec<&Node> {
+        let mut children: Vec<&Node> = self.current_node().children.iter().collect();
+        children.sort_by(|a, b| {
+            self.measure(b)
+                .cmp(&self.measure(a))
+                .then_with(|| compare_natural(&a.name(), &b.name()))
+        });
+        children
+    }
+
+    fn selected(&self) -> Option<&Node> {
+        self.list
+            .selected()
+            .and_then(|index| self.children().get(index).copied())
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let count: usize = self.children().len();
+        if count == 0 {
+            self.list.select(None);
+            return;
+        }
+        let current: isize = self.list.selected().unwrap_or(0) as isize;
+        let next: isize = (current + delta).clamp(0, count as isize - 1);
+        self.list.select(Some(next as usize));
+    }
+
+    /// Open the selected directory
+    fn enter(&mut self) {
+        let path: PathBuf = match self.selected() {
+            Some(node) if node.is_dir => node.path.clone(),
+            _ => return,
+        };
+        self.current = path;
+        self.list.select(Some(0));
+    }
+
+    /// Go back to the parent directory, selecting the directory we came from
+    fn leave(&mut self) {
+        if self.current == self.root.path {
+            return;
+        }
+        let left: PathBuf = self.current.clone();
+        self.current = left
+            .parent()
+            .map_or(self.root.path.clone(), Path::to_path_buf);
+        let index: Option<usize> = self.children().iter().position(|node| node.path == left);
+        self.list.select(Some(index.unwrap_or(0)));
+    }
+
+    fn toggle_mark(&mut self) {
+        let path: PathBuf = match self.selected() {
+            Some(node) => node.path.clone(),
+            None => return,
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+    }
+
+    /// Delete the marked entries from disk and from the tree
+    fn delete(&mut self) {
+        let mut deleted: usize = 0;
+        let mut failed: Vec<String> = Vec::new();
+        for path in std::mem::take(&mut self.marked) {
+            let is_dir: bool = match self.root.find(&path) {
+                Some(node) => node.is_dir,
+                // Already deleted along with a marked parent
+                None => continue,
+            };
+            let result: io::Result<()> = match is_dir {
+                true => fs::remove_dir_all(&path),
+                false => fs::remove_file(&path),
+            };
+            match result {
+                Ok(()) => {
+                    self.root.remove(&path);
+                    deleted += 1;
+                }
+                Err(error) => failed.push(format!("{}: {}", escape_filename(&path), error)),
+            }
+        }
+
+        // The listed directory may have been inside a deleted one
+        while self.root.find(&self.current).is_none() {
+            self.current = match self.current.parent() {
+                Some(parent) => parent.to_path_buf(),
+                None => self.root.path.clone(),
+            };
+        }
+        self.move_selection(0);
+
+        self.status = match failed.is_empty() {
+            true => format!("Deleted {} items", deleted),
+            false => format!(
+                "Deleted {} items, {} failed: {}",
+                deleted,
+                failed.len(),
+                failed.join("; ")
+            ),
+        };
+    }
+
+    fn format_size(&self, bytes: u64) -> String {
+        FileSize {
+            bytes,
+            format: self.size_format,
+            si: self.si,
+        }
+        .to_string()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, help_bar]: [Rect; 3] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+        let current: &Node = self.current_node();
+        let measure_name: &str = match self.disk_usage {
+            true => "disk usage",
+            false => "apparent size",
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{}  {} ({}, {} files)",
+                escape_filename(&current.path),
+                self.format_size(self.measure(current)),
+                measure_name,
+                current.file_count
+            ))
+            .bold(),
+            header,
+        );
+
+        let children: Vec<&Node> = self.children();
+        let largest: u64 = children.first().map_or(0, |node| self.measure(node)).max(1);
+        let items: Vec<ListItem> = children
+            .iter()
+            .map(|node| {
+                let filled: usize = (self.measure(node) as f64 / largest as f64 * BAR_WIDTH as f64)
+                    .round() as usize;
+                let mark: &str = match self.marked.contains(&node.path) {
+                    true => "*",
+                    false => " ",
+                };
+                let name: String = match node.is_dir {
+                    true => format!("{}/", node.name()),
+                    false => node.name(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::from(format!(
+                        "{}{:>12} ",
+                        mark,
+                        self.format_size(self.measure(node))
+                    )),
+                    Span::from(format!(
+                        "[{}{}] ",
+                        "#".repeat(filled),
+                        " ".repeat(BAR_WIDTH - filled)
+                    ))
+                    .dim(),
+                    match node.is_dir {
+                        true => Span::from(name).bold(),
+                        false => Span::from(name),
+                    },
+                ]))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            body,
+            &mut self.list,
+        );
+
+        let help: String = match self.status.is_empty() {
+            true => "↑/↓ move  →/Enter open  ← back  a apparent/disk  space mark  d delete  q quit"
+                .to_string(),
+            false => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(help).dim(), help_bar);
+
+        if self.mode == Mode::ConfirmDelete {
+            self.draw_confirmation(frame);
+        }
+    }
+
+    fn draw_confirmation(&self, frame: &mut Frame) {
+        let total: u64 = self
+            .marked
+            .iter()
+            .filter_map(|path| self.root.find(path))
+            .map(|node| self.measure(node))
+            .sum();
+        let mut lines: Vec<Line> = vec![Line::from(format!(
+            "Permanently delete {} items ({})?",
+            self.marked.len(),
+            self.format_size(total)
+        ))];
+        lines.extend(
+            self.marked
+                .iter()
+                .take(5)
+                .map(|path| Line::from(format!("  {}", escape_filename(path)))),
+        );
+        if self.marked.len() > 5 {
+            lines.push(Line::from(format!("  and {} more", self.marked.len() - 5)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("y to delete, any other key to cancel").dim());
+
+        let area: Rect = frame.area();
+        let height: u16 = (lines.len() as u16 + 2).min(area.height);
+        let width: u16 = (area.width * 3 / 4).max(20).min(area.width);
+        let popup: Rect = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Delete ")),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scan;
+    use tempfile::TempDir;
+
+    /// A browser over a temporary directory holding `a.txt`, `sub/b.txt` and `sub/deep/c.txt`
+    fn app() -> io::Result<(TempDir, App)> {
+        let dir: TempDir = tempfile::tempdir()?;
+        fs::create_dir_all(dir.path().join("sub/deep"))?;
+        fs::write(dir.path().join("a.txt"), "hello")?;
+        fs::write(dir.path().join("sub/b.txt"), "0123456789")?;
+        fs::write(dir.path().join("sub/deep/c.txt"), "xyz")?;
+        let root: Node =
+            Node::scan_dir(dir.path(), &Scan::new(true, 1, false)).map_err(|error| error.error)?;
+        Ok((dir, App::new(root, FileSizeFormat::B, false)))
+    }
+
+    #[test]
+    fn cancelling_a_delete_unmarks_the_implicitly_marked_entry() -> io::Result<()> {
+        let (dir, mut app) = app()?;
+        // The largest entry, `sub`, is listed first
+        app.handle_key(KeyCode::Char('d'));
+        assert!(app.mode == Mode::ConfirmDelete);
+        assert_eq!(Vec::from_iter(&app.marked), [&dir.path().join("sub")]);
+        app.handle_key(KeyCode::Char('n'));
+        assert!(app.mode == Mode::Browse);
+        assert!(app.marked.is_empty());
+        assert_eq!(app.status, "Deletion cancelled");
+
+        // Entries marked explicitly stay marked
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Char('d'));
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(Vec::from_iter(&app.marked), [&dir.path().join("sub")]);
+        assert!(dir.path().join("sub").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn deleting_a_marked_parent_and_child_deletes_both() -> io::Result<()> {
+        let (dir, mut app) = app()?;
+        app.marked.insert(dir.path().join("sub"));
+        app.marked.insert(dir.path().join("sub/b.txt"));
+        app.handle_key(KeyCode::Char('d'));
+        app.handle_key(KeyCode::Char('y'));
+
+        assert_eq!(app.status, "Deleted 1 items");
+        assert!(app.marked.is_empty());
+        assert!(!dir.path().join("sub").exists());
+        assert!(app.root.find(&dir.path().join("sub/b.txt")).is_none());
+        assert_eq!((app.root.size, app.root.file_count), (5, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn deleting_the_listed_directory_moves_back_up() -> io::Result<()> {
+        let (dir, mut app) = app()?;
+        app.current = dir.path().join("sub/deep");
+
+        app.marked.insert(dir.path().join("sub"));
+        app.delete();
+        assert_eq!(app.current, dir.path());
+        assert_eq!(
+            app.selected().map(|node| node.name()),
+            Some("a.txt".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,683 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use grrs::block::BlockSearcher;
+use grrs::delimited::CsvSearcher;
+use grrs::field::{Condition, FieldMatcher};
+#[cfg(feature = "git")]
+use grrs::history::{CommitMatch, History};
+use grrs::index::Index;
+use grrs::json::{InvalidJson, JsonPath, JsonSearcher};
+use grrs::matcher::{FuzzyMatcher, Matcher, RegexMatcher, SubstringMatcher};
+use grrs::query::Query;
+use grrs::search::Searcher;
+use grrs::secrets::{builtin_rules, load_baseline, to_sarif, Finding, Rule, SecretScanner};
+use grrs::sink::{CollectingSink, FilesWithMatchesSink, JsonSink, Sink, StandardSink, VimgrepSink};
+use grrs::stats::Stats;
+use grrs::types::FileTypes;
+use regex::Regex;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+mod tui;
+
+/// Environment variable pointing at the config file, overriding the default location
+const CONFIG_PATH_VAR: &str = "GRRS_CONFIG_PATH";
+
+#[derive(Parser)]
+#[command(subcommand_negates_reqs = true, args_override_self = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    pattern: Option<String>,
+    #[arg(required_unless_present_any = ["query", "from", "field"])]
+    path: Option<PathBuf>,
+    /// Treat the pattern as a regular expression instead of a fixed string
+    #[arg(short, long)]
+    regex: bool,
+    /// Match the pattern regardless of case
+    #[arg(short, long, overrides_with_all = ["smart_case", "case_sensitive"])]
+    ignore_case: bool,
+    /// Ignore case unless the pattern contains an uppercase letter
+    #[arg(short = 'S', long, overrides_with_all = ["ignore_case", "case_sensitive"])]
+    smart_case: bool,
+    /// Match the pattern case sensitively, the default, overriding `--ignore-case` or `--smart-case`
+    /// from the config file
+    #[arg(short = 's', long, overrides_with_all = ["ignore_case", "smart_case"])]
+    case_sensitive: bool,
+    /// Match lines containing the pattern with at most this many typos (Levenshtein distance)
+    #[arg(long, value_name = "K", conflicts_with = "regex")]
+    fuzzy: Option<usize>,
+    /// Search with a boolean query such as `timeout AND retry AND NOT test` instead of a pattern.
+    /// `FILE(...)` tests a sub-query against the whole file rather than the line
+    #[arg(short, long, value_name = "QUERY")]
+    query: Option<String>,
+    /// Print blocks of lines starting at a line matching this pattern, instead of searching for a pattern
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        requires = "to",
+        conflicts_with = "query"
+    )]
+    from: Option<String>,
+    /// End each block started by `--from` at the next line matching this pattern
+    #[arg(long, value_name = "PATTERN", requires = "from")]
+    to: Option<String>,
+    /// Match lines whose `key: value` or logfmt `key=value` field has this key, instead of searching for
+    /// a pattern. A condition can be given inline, as in `level=error` or `bar>15`
+    #[arg(long, value_name = "KEY", conflicts_with_all = ["query", "from"])]
+    field: Option<String>,
+    /// Condition the `--field` value must satisfy, such as `>15`, `<=0.5`, `!=debug` or `error`.
+    /// Numbers are compared numerically
+    #[arg(long = "where", value_name = "CONDITION", requires = "field")]
+    where_: Option<String>,
+    /// Treat each line as a JSON record and match the pattern against the value at this path,
+    /// such as `$.request.status`
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["query", "from", "field"])]
+    json_path: Option<String>,
+    /// With `--json-path`, print the extracted value instead of the whole record
+    #[arg(long, requires = "json_path")]
+    print_value: bool,
+    /// With `--json-path`, what to do with lines that aren't valid JSON
+    #[arg(
+        value_enum,
+        long,
+        value_name = "ACTION",
+        default_value_t,
+        requires = "json_path"
+    )]
+    invalid_json: InvalidJsonArg,
+    /// Parse input as CSV and match the pattern against individual fields, printing the header and
+    /// the matching rows
+    #[arg(long, conflicts_with_all = ["query", "from", "field", "json_path"])]
+    csv: bool,
+    /// Like `--csv`, for tab-separated input
+    #[arg(long, conflicts_with_all = ["csv", "query", "from", "field", "json_path"])]
+    tsv: bool,
+    /// Field delimiter to use with `--csv`
+    #[arg(long, value_name = "CHAR", requires = "csv")]
+    delimiter: Option<char>,
+    /// With `--csv` or `--tsv`, only match against this column, by header name. Can be repeated
+    #[arg(long, value_name = "NAME")]
+    column: Vec<String>,
+    /// Only print the paths of files containing matches
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+    /// Print results as JSON lines, including the edit distance of fuzzy matches
+    #[arg(long, conflicts_with = "files_with_matches")]
+    json: bool,
+    /// Print one `path:line:column:text` line per match, for editors such as vim. Not available with
+    /// `--json-path`, `--csv` or `--tsv`, whose matches aren't at a column of the line
+    #[arg(long, conflicts_with_all = ["files_with_matches", "json", "json_path", "csv", "tsv"])]
+    vimgrep: bool,
+    /// Print each file's path once above its matches instead of on every line
+    #[arg(long, conflicts_with_all = ["files_with_matches", "json", "vimgrep"])]
+    heading: bool,
+    /// Follow paths with a NUL byte instead of `:` or a newline, for use with `xargs -0`
+    #[arg(short = '0', long, conflicts_with = "json")]
+    null: bool,
+    /// Number of lines to show after each match
+    #[arg(short = 'A', long, value_name = "NUM")]
+    after_context: Option<usize>,
+    /// Number of lines to show before each match
+    #[arg(short = 'B', long, value_name = "NUM")]
+    before_context: Option<usize>,
+    /// Number of lines to show before and after each match
+    #[arg(short = 'C', long, value_name = "NUM")]
+    context: Option<usize>,
+    /// Print the number of files searched and matched, lines matched, bytes searched and the time taken
+    /// at the end. Included as a `summary` record in `--json` output
+    #[arg(long)]
+    stats: bool,
+    /// Keep the file open and print new matching lines as they are appended
+    #[arg(short, long)]
+    follow: bool,
+    /// Use the trigram index built by `grrs index build` to skip files that can't match
+    #[arg(long)]
+    index: bool,
+    /// Browse the results in a terminal interface with a preview pane, editing the pattern live and
+    /// opening matches in `$EDITOR`
+    #[arg(
+        long,
+        conflicts_with_all = ["query", "from", "field", "follow", "json", "vimgrep", "files_with_matches", "stats"]
+    )]
+    interactive: bool,
+    /// Search the files as they were at this git revision, such as `HEAD~10`, a tag or a commit id,
+    /// instead of the work tree. Paths are printed as `REV:path`
+    #[arg(
+        long,
+        value_name = "REV",
+        conflicts_with_all = ["from", "json_path", "csv", "tsv", "follow", "index", "interactive"]
+    )]
+    rev: Option<String>,
+    /// List the commits that added or removed lines matching the pattern, starting from `--rev` or
+    /// `HEAD`, like `git log -S`
+    #[arg(
+        long,
+        conflicts_with_all = ["query", "from", "json_path", "csv", "tsv", "follow", "index", "interactive", "files_with_matches", "json", "vimgrep", "heading", "stats", "context", "before_context", "after_context"]
+    )]
+    log: bool,
+    /// Only search files of this type, such as `rust` or `md`, when searching a directory. Can be repeated
+    #[arg(short = 't', long = "type", value_name = "TYPE", conflicts_with_all = ["rev", "log"])]
+    type_: Vec<String>,
+    /// Define a file type, or add a glob to an existing one, as in `proto:*.proto`. Usually set in the
+    /// config file. Can be repeated
+    #[arg(long, value_name = "NAME:GLOB")]
+    type_add: Vec<String>,
+    /// Don't read default arguments from the config file at `$GRRS_CONFIG_PATH` or
+    /// `~/.config/grrs/config`
+    #[arg(long)]
+    no_config: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the trigram index used by `--index`
+    #[command(subcommand)]
+    Index(IndexCommand),
+    /// Scan for secrets such as cloud credentials, private keys and tokens with the built-in rules.
+    /// Exits with status 1 when anything is found, and 2 when the scan fails
+    Secrets(SecretsArgs),
+}
+
+#[derive(Args)]
+struct SecretsArgs {
+    /// File or directory to scan
+    path: PathBuf,
+    /// How to print findings
+    #[arg(value_enum, long, default_value_t)]
+    format: SecretsFormat,
+    /// Only run the rule with this ID, such as `aws-access-key-id`. Can be repeated
+    #[arg(long, value_name = "ID")]
+    rule: Vec<String>,
+    /// Minimum Shannon entropy in bits per character, replacing the threshold of every rule that has one
+    #[arg(long, value_name = "BITS")]
+    min_entropy: Option<f64>,
+    /// Ignore findings whose secret or path matches this regular expression. Can be repeated.
+    /// Lines containing `grrs:allow` are always ignored
+    #[arg(long, value_name = "REGEX")]
+    allow: Vec<String>,
+    /// Don't report findings listed in this file, written earlier with `--format json`
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+}
+
+/// Output formats of `grrs secrets`
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+enum SecretsFormat {
+    /// `path:line:column: rule secret`, with the secret masked
+    #[default]
+    Text,
+    /// One JSON object per finding, usable as a baseline
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+/// Values of `--invalid-json`, mirroring `InvalidJson`
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+enum InvalidJsonArg {
+    /// Ignore them
+    Skip,
+    /// Print a warning with the line number to stderr and carry on
+    #[default]
+    Report,
+    /// Stop with an error
+    Fail,
+}
+
+impl From<InvalidJsonArg> for InvalidJson {
+    fn from(arg: InvalidJsonArg) -> InvalidJson {
+        match arg {
+            InvalidJsonArg::Skip => InvalidJson::Skip,
+            InvalidJsonArg::Report => InvalidJson::Report,
+            InvalidJsonArg::Fail => InvalidJson::Fail,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Build the index for a directory, or update it for files that changed since the last build
+    Build { dir: PathBuf },
+}
+
+fn main() -> Result<()> {
+    let args = Cli::parse_from(args_with_config()?);
+
+    if let Some(Command::Index(IndexCommand::Build { dir })) = &args.command {
+        let summary = Index::build(dir)
+            .with_context(|| format!("could not build index for `{}`", dir.display()))?;
+        println!(
+            "Indexed {} files ({} updated)",
+            summary.files, summary.updated
+        );
+        return Ok(());
+    }
+    if let Some(Command::Secrets(secrets)) = &args.command {
+        // Like grep, findings exit with 1 and errors with 2, so scripts can tell them apart
+        match scan_secrets(secrets) {
+            Ok(found) => std::process::exit(found as i32),
+            Err(error) => {
+                eprintln!("Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let (pattern, path): (Option<String>, PathBuf) = resolve_positionals(&args)?;
+
+    #[cfg(not(feature = "git"))]
+    if args.log || args.rev.is_some() {
+        bail!("--rev and --log require grrs to be built with the `git` feature");
+    }
+    #[cfg(feature = "git")]
+    if args.log {
+        let matcher: Box<dyn Matcher> = match pattern.as_deref() {
+            Some(pattern) => build_matcher(pattern, &args)?,
+            None => build_field_matcher(&args)?,
+        };
+        let history: History = History::open(&path)
+            .with_context(|| format!("could not open git repository at `{}`", path.display()))?;
+        let rev: &str = args.rev.as_deref().unwrap_or("HEAD");
+        return history
+            .search_log(rev, &matcher, print_commit)
+            .with_context(|| format!("could not search the history of `{}`", rev));
+    }
+
+    let mut sink: Box<dyn Sink> = if args.json {
+        Box::new(JsonSink::new(std::io::stdout()))
+    } else if args.files_with_matches {
+        Box::new(FilesWithMatchesSink::new(std::io::stdout()).with_null(args.null))
+    } else if args.vimgrep {
+        Box::new(VimgrepSink::new(std::io::stdout()).with_null(args.null))
+    } else {
+        Box::new(
+            StandardSink::new(std::io::stdout())
+                .with_path(path.is_dir() || args.rev.is_some())
+                .with_heading(args.heading)
+                .with_null(args.null),
+        )
+    };
+    let index: Option<Index> = match args.index && path.is_dir() {
+        true => Some(Index::load(&path).with_context(|| {
+            format!(
+                "could not load index for `{}`, run `grrs index build` first",
+                path.display()
+            )
+        })?),
+        false => None,
+    };
+    let types: FileTypes = build_types(&args)?;
+
+    if args.interactive {
+        return tui::run(pattern.as_deref().unwrap_or_default(), |pattern| {
+            let mut sink: CollectingSink = CollectingSink::new();
+            search(
+                &args,
+                Some(pattern),
+                &path,
+                index.as_ref(),
+                &types,
+                &mut sink,
+            )?;
+            Ok(sink.files)
+        });
+    }
+
+    let started: Instant = Instant::now();
+    let mut stats: Stats = search(
+        &args,
+        pattern.as_deref(),
+        &path,
+        index.as_ref(),
+        &types,
+        &mut sink,
+    )?;
+    stats.elapsed = started.elapsed();
+
+    if args.stats {
+        match args.json {
+            true => sink.summary(&stats)?,
+            false => print_stats(&stats),
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the search selected by the arguments and return its stats.
+/// Follow mode never returns unless it fails.
+fn search(
+    args: &Cli,
+    pattern: Option<&str>,
+    path: &Path,
+    index: Option<&Index>,
+    types: &FileTypes,
+    sink: &mut impl Sink,
+) -> Result<Stats> {
+    if let (Some(from), Some(to)) = (&args.from, &args.to) {
+        if args.follow {
+            bail!("--follow can't be combined with --from and --to");
+        }
+        let searcher = BlockSearcher {
+            from: build_matcher(from, args)?,
+            to: build_matcher(to, args)?,
+        };
+        return match path.is_dir() {
+            true => searcher.search_dir(path, index, Some(types), sink),
+            false => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("could not read file `{}`", path.display()))?;
+                searcher.search_str(Some(path), &content, sink)
+            }
+        }
+        .with_context(|| format!("could not search `{}`", path.display()));
+    }
+
+    if let (Some(json_path), Some(pattern)) = (&args.json_path, pattern) {
+        if args.follow {
+            bail!("--follow can't be combined with --json-path");
+        }
+        let searcher = JsonSearcher {
+            path: JsonPath::parse(json_path)?,
+            matcher: build_matcher(pattern, args)?,
+            print_value: args.print_value,
+            invalid: args.invalid_json.into(),
+        };
+        return match path.is_dir() {
+            true => searcher.search_dir(path, Some(types), sink),
+            false => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("could not read file `{}`", path.display()))?;
+                searcher.search_str(Some(path), &content, sink)
+            }
+        }
+        .with_context(|| format!("could not search `{}`", path.display()));
+    }
+
+    if let (true, Some(pattern)) = (args.csv || args.tsv, pattern) {
+        if args.follow {
+            bail!("--follow can't be combined with --csv or --tsv");
+        }
+        let delimiter: char = match args.tsv {
+            true => '\t',
+            false => args.delimiter.unwrap_or(','),
+        };
+        if !delimiter.is_ascii() {
+            bail!("the delimiter must be a single ASCII character");
+        }
+        let searcher = CsvSearcher {
+            matcher: build_matcher(pattern, args)?,
+            columns: args.column.clone(),
+            delimiter: delimiter as u8,
+        };
+        return match path.is_dir() {
+            true => searcher.search_dir(path, index, Some(types), sink),
+            false => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("could not read file `{}`", path.display()))?;
+                searcher.search_str(Some(path), &content, sink)
+            }
+        }
+        .with_context(|| format!("could not search `{}`", path.display()));
+    } else if !args.column.is_empty() {
+        bail!("--column requires --csv or --tsv");
+    }
+
+    let matcher: Box<dyn Matcher> = match (&args.query, pattern) {
+        (Some(query), _) => build_query(query, args)?,
+        (None, None) if args.field.is_some() => build_field_matcher(args)?,
+        (None, Some(pattern)) => build_matcher(pattern, args)?,
+        (None, None) => unreachable!("a pattern is required without --query or --field"),
+    };
+    let searcher = Searcher {
+        before_context: args.before_context.or(args.context).unwrap_or(0),
+        after_context: args.after_context.or(args.context).unwrap_or(0),
+    };
+
+    if args.follow {
+        grrs::follow::follow(path, &matcher, sink, Duration::from_millis(250))
+            .with_context(|| format!("could not follow file `{}`", path.display()))?;
+        unreachable!("following a file only stops on errors");
+    }
+
+    #[cfg(feature = "git")]
+    if let Some(rev) = &args.rev {
+        let history: History = History::open(path)
+            .with_context(|| format!("could not open git repository at `{}`", path.display()))?;
+        return history
+            .search_rev(rev, &searcher, &matcher, sink)
+            .with_context(|| format!("could not search revision `{}`", rev));
+    }
+
+    if path.is_dir() {
+        return searcher
+            .search_dir(&matcher, path, index, Some(types), sink)
+            .with_context(|| format!("could not search directory `{}`", path.display()));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read file `{}`", path.display()))?;
+
+    Ok(searcher.search_str(&matcher, Some(path), &content, sink)?)
+}
+
+/// Build the file types from the built-in ones, the `--type-add` definitions and the `--type` selection
+fn build_types(args: &Cli) -> Result<FileTypes> {
+    let mut types: FileTypes = FileTypes::new();
+    for definition in &args.type_add {
+        types.add(definition)?;
+    }
+    for name in &args.type_ {
+        types.select(name)?;
+    }
+    Ok(types)
+}
+
+/// Run `grrs secrets` and print its findings, returning whether there were any
+fn scan_secrets(args: &SecretsArgs) -> Result<bool> {
+    let mut rules: Vec<Rule> = builtin_rules();
+    if let Some(unknown) = args
+        .rule
+        .iter()
+        .find(|id| !rules.iter().any(|rule| rule.id == id.as_str()))
+    {
+        bail!("no built-in rule with ID `{}`", unknown);
+    }
+    if !args.rule.is_empty() {
+        rules.retain(|rule| args.rule.iter().any(|id| id == rule.id));
+    }
+
+    let mut scanner: SecretScanner = SecretScanner::new(rules);
+    scanner.min_entropy = args.min_entropy;
+    scanner.allow = args
+        .allow
+        .iter()
+        .map(|allow| {
+            Regex::new(allow).with_context(|| format!("invalid --allow pattern `{}`", allow))
+        })
+        .collect::<Result<_>>()?;
+    if let Some(baseline) = &args.baseline {
+        scanner.baseline = load_baseline(baseline)
+            .with_context(|| format!("could not read baseline `{}`", baseline.display()))?;
+    }
+
+    let (findings, _): (Vec<Finding>, Stats) = scanner
+        .scan_path(&args.path)
+        .with_context(|| format!("could not scan `{}`", args.path.display()))?;
+    let mut out = std::io::stdout().lock();
+    match args.format {
+        SecretsFormat::Text => {
+            for finding in &findings {
+                writeln!(
+                    out,
+                    "{}:{}:{}: {} {}",
+                    finding.path.display(),
+                    finding.line_number,
+                    finding.column,
+                    finding.rule_id,
+                    finding.redacted
+                )?;
+            }
+        }
+        SecretsFormat::Json => {
+            for finding in &findings {
+                writeln!(out, "{}", serde_json::to_string(finding)?)?;
+            }
+        }
+        SecretsFormat::Sarif => {
+            writeln!(
+                out,
+                "{}",
+                serde_json::to_string_pretty(&to_sarif(&scanner.rules, &findings))?
+            )?;
+        }
+    }
+    out.flush()?;
+    Ok(!findings.is_empty())
+}
+
+/// Print the stats of a search as a short text summary
+fn print_stats(stats: &Stats) {
+    println!();
+    println!("{} files searched", stats.files_searched);
+    println!("{} files matched", stats.files_matched);
+    println!("{} lines matched", stats.lines_matched);
+    println!("{} bytes searched", stats.bytes_searched);
+    println!("{:.6} seconds elapsed", stats.elapsed.as_secs_f64());
+}
+
+/// Print a commit found by `--log` in the style of `git log`, followed by its matching lines as
+/// `path:+line` or `path:-line`
+#[cfg(feature = "git")]
+fn print_commit(commit: &CommitMatch) -> std::io::Result<()> {
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "commit {}", commit.id)?;
+    writeln!(out, "Author: {}", commit.author)?;
+    writeln!(
+        out,
+        "Date:   {}",
+        commit.time.format("%Y-%m-%d %H:%M:%S %z")
+    )?;
+    writeln!(out)?;
+    writeln!(out, "    {}", commit.summary)?;
+    writeln!(out)?;
+    for change in &commit.changes {
+        let sign: char = match change.added {
+            true => '+',
+            false => '-',
+        };
+        writeln!(out, "{}:{}{}", change.path.display(), sign, change.line)?;
+    }
+    writeln!(out)
+}
+
+/// Split the positional arguments into the pattern and the path to search.
+/// Modes that don't take a pattern, like `--query`, `--from` and `--field`, only get a path, which clap
+/// puts in the first slot.
+fn resolve_positionals(args: &Cli) -> Result<(Option<String>, PathBuf)> {
+    let takes_pattern: bool = args.query.is_none() && args.from.is_none() && args.field.is_none();
+    match (takes_pattern, &args.pattern, &args.path) {
+        (false, Some(path), None) => Ok((None, PathBuf::from(path))),
+        (false, _, _) => bail!("a pattern can't be combined with --query, --from or --field"),
+        (true, pattern, Some(path)) => Ok((pattern.clone(), path.clone())),
+        (true, _, None) => bail!("a path to search is required"),
+    }
+}
+
+/// Build the matcher for a pattern, as a regular expression, a fuzzy pattern or a fixed string
+fn build_matcher(pattern: &str, args: &Cli) -> Result<Box<dyn Matcher>> {
+    let case_insensitive: bool =
+        args.ignore_case || (args.smart_case && !pattern.chars().any(char::is_uppercase));
+    Ok(match (args.regex, args.fuzzy) {
+        (true, _) => Box::new(RegexMatcher::with_case_insensitive(
+            pattern,
+            case_insensitive,
+        )?),
+        (false, Some(max_distance)) => Box::new(FuzzyMatcher::with_case_insensitive(
+            pattern,
+            max_distance,
+            case_insensitive,
+        )?),
+        (false, None) if case_insensitive => {
+            Box::new(RegexMatcher::literal_case_insensitive(pattern))
+        }
+        (false, None) => Box::new(SubstringMatcher::new(pattern)),
+    })
+}
+
+/// Parse the query for `--query`, ignoring case in its terms with `--ignore-case`, or with `--smart-case`
+/// when no term has an uppercase letter
+fn build_query(query: &str, args: &Cli) -> Result<Box<dyn Matcher>> {
+    let query: Query = Query::parse(query).context("could not parse query")?;
+    let case_insensitive: bool =
+        args.ignore_case || (args.smart_case && !query.has_uppercase_term());
+    Ok(match case_insensitive {
+        true => Box::new(query.case_insensitive()),
+        false => Box::new(query),
+    })
+}
+
+/// The arguments to parse: default arguments from the config file, followed by the command line so it
+/// takes precedence. Subcommands and `--no-config` skip the config file.
+fn args_with_config() -> Result<Vec<OsString>> {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+
+    let is_subcommand: bool = args
+        .get(1)
+        .and_then(|arg| arg.to_str())
+        .is_some_and(|arg| Cli::command().find_subcommand(arg).is_some());
+    let no_config: bool = args
+        .iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--no-config");
+    if is_subcommand || no_config {
+        return Ok(args);
+    }
+
+    let config: Vec<OsString> = match std::env::var_os(CONFIG_PATH_VAR) {
+        Some(path) => {
+            let path: PathBuf = PathBuf::from(path);
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("could not read config file `{}`", path.display()))?;
+            parse_config(&content)
+        }
+        None => match default_config_path().map(std::fs::read_to_string) {
+            Some(Ok(content)) => parse_config(&content),
+            _ => Vec::new(),
+        },
+    };
+    args.splice(1..1, config);
+    Ok(args)
+}
+
+/// The config file location used when `GRRS_CONFIG_PATH` isn't set
+fn default_config_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".config/grrs/config"))
+}
+
+/// Read one argument per line, ignoring blank lines and `#` comments.
+/// Options taking a value are written as `--option=value` or with the value on the next line.
+fn parse_config(content: &str) -> Vec<OsString> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(OsString::from)
+        .collect()
+}
+
+/// Build the matcher for `--field`, taking the condition from `--where` or from the field itself
+fn build_field_matcher(args: &Cli) -> Result<Box<dyn Matcher>> {
+    let field: &str = args.field.as_deref().unwrap_or_default();
+    let matcher: FieldMatcher = match &args.where_ {
+        Some(condition) => FieldMatcher::new(field, Condition::parse(condition)?)?,
+        None => FieldMatcher::parse(field)?,
+    };
+    Ok(Box::new(matcher))
+}
@@ -0,0 +1,315 @@
+//! Interactive terminal browser for search results, started with `--interactive`.
+
+use anyhow::{Context, Result};
+use grrs::sink::FileMatches;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Number of lines shown above and below the selected match in the preview pane
+const PREVIEW_CONTEXT: usize = 20;
+
+/// One row of the result list: either a file heading or a match within the file above it
+enum Row {
+    File(PathBuf),
+    Match {
+        path: PathBuf,
+        line_number: usize,
+        line: String,
+    },
+}
+
+/// Whether keys move through results or edit the pattern
+#[derive(PartialEq)]
+enum Mode {
+    Browse,
+    Edit,
+}
+
+struct App<F: Fn(&str) -> Result<Vec<FileMatches>>> {
+    search: F,
+    pattern: String,
+    mode: Mode,
+    rows: Vec<Row>,
+    list: ListState,
+    status: String,
+    preview_path: Option<PathBuf>,
+    preview_lines: Vec<String>,
+}
+
+/// Run the result browser until the user quits. `search` runs the library search for a pattern and is
+/// called again every time the pattern is edited.
+pub fn run(pattern: &str, search: impl Fn(&str) -> Result<Vec<FileMatches>>) -> Result<()> {
+    let mut app = App {
+        search,
+        pattern: pattern.to_string(),
+        mode: Mode::Browse,
+        rows: Vec::new(),
+        list: ListState::default(),
+        status: String::new(),
+        preview_path: None,
+        preview_lines: Vec::new(),
+    };
+    app.refresh();
+
+    let mut terminal: DefaultTerminal =
+        ratatui::try_init().context("could not start the interface")?;
+    let result: Result<()> = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<F: Fn(&str) -> Result<Vec<FileMatches>>> App<F> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            self.load_preview();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key: KeyEvent = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+
+            match self.mode {
+                Mode::Edit => match key.code {
+                    KeyCode::Esc | KeyCode::Enter => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        self.pattern.pop();
+                        self.refresh();
+                    }
+                    KeyCode::Char(c) => {
+                        self.pattern.push(c);
+                        self.refresh();
+                    }
+                    _ => (),
+                },
+                Mode::Browse => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('/') => self.mode = Mode::Edit,
+                    KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                    KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                    KeyCode::PageDown => self.move_selection(10),
+                    KeyCode::PageUp => self.move_selection(-10),
+                    KeyCode::Enter | KeyCode::Char('e') => self.open_in_editor(terminal)?,
+                    _ => (),
+                },
+            }
+        }
+    }
+
+    /// Search again for the current pattern, keeping the previous results if the pattern is invalid
+    fn refresh(&mut self) {
+        let files: Vec<FileMatches> = match (self.search)(&self.pattern) {
+            Ok(files) => files,
+            Err(error) => {
+                self.status = format!("{:#}", error);
+                return;
+            }
+        };
+
+        self.rows.clear();
+        let mut match_count: usize = 0;
+        for file in files {
+            let path: PathBuf = file.path.unwrap_or_default();
+            self.rows.push(Row::File(path.clone()));
+            for (line_number, line, _) in file.matches {
+                match_count += 1;
+                self.rows.push(Row::Match {
+                    path: path.clone(),
+                    line_number,
+                    line,
+                });
+            }
+        }
+        self.status = format!("{} matches", match_count);
+        self.list.select(None);
+        self.move_selection(1);
+    }
+
+    /// Move the selection by `delta` rows, skipping file headings
+    fn move_selection(&mut self, delta: isize) {
+        let matches: Vec<usize> = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, Row::Match { .. }))
+            .map(|(index, _)| index)
+            .collect();
+        if matches.is_empty() {
+            self.list.select(None);
+            return;
+        }
+
+        let current: isize = match self.list.selected() {
+            Some(selected) => matches
+                .iter()
+                .position(|&index| index == selected)
+                .unwrap_or(0) as isize,
+            None => -1,
+        };
+        let next: usize = (current + delta).clamp(0, matches.len() as isize - 1) as usize;
+        self.list.select(Some(matches[next]));
+    }
+
+    fn selected(&self) -> Option<(&Path, usize)> {
+        match self.list.selected().and_then(|index| self.rows.get(index)) {
+            Some(Row::Match {
+                path, line_number, ..
+            }) => Some((path, *line_number)),
+            _ => None,
+        }
+    }
+
+    /// Read the file of the selected match for the preview pane, if it isn't loaded already
+    fn load_preview(&mut self) {
+        let path: PathBuf = match self.selected() {
+            Some((path, _)) => path.to_path_buf(),
+            None => return,
+        };
+        if self.preview_path.as_ref() == Some(&path) {
+            return;
+        }
+        self.preview_lines = match std::fs::read_to_string(&path) {
+            Ok(content) => content.lines().map(str::to_string).collect(),
+            Err(error) => vec![format!("could not read file: {}", error)],
+        };
+        self.preview_path = Some(path);
+    }
+
+    /// Open the selected match in `$EDITOR` at its line, suspending the interface while it runs
+    fn open_in_editor(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let (path, line_number): (PathBuf, usize) = match self.selected() {
+            Some((path, line_number)) => (path.to_path_buf(), line_number),
+            None => return Ok(()),
+        };
+        // Like git, the editor may come with arguments, as in `code --wait`
+        let editor: String = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_default();
+        let mut words = editor.split_whitespace();
+        let program: &str = words.next().unwrap_or("vi");
+
+        disable_raw_mode()?;
+        execute!(std::io::stdout(), LeaveAlternateScreen)?;
+        let status = Command::new(program)
+            .args(words)
+            .arg(format!("+{}", line_number))
+            .arg(&path)
+            .status()
+            .with_context(|| format!("could not start editor `{}`", editor));
+        execute!(std::io::stdout(), EnterAlternateScreen)?;
+        enable_raw_mode()?;
+        terminal.clear()?;
+
+        if let Err(error) = status {
+            self.status = format!("{:#}", error);
+        }
+        // The file may have been changed in the editor
+        self.preview_path = None;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, body, help_bar]: [Rect; 3] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+        let [results, preview]: [Rect; 2] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(body);
+
+        let input_style: Style = match self.mode {
+            Mode::Edit => Style::default().add_modifier(Modifier::BOLD),
+            Mode::Browse => Style::default(),
+        };
+        frame.render_widget(
+            Paragraph::new(self.pattern.as_str())
+                .style(input_style)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" Pattern ({}) ", self.status)),
+                ),
+            input,
+        );
+        if self.mode == Mode::Edit {
+            frame.set_cursor_position((
+                input.x + 1 + self.pattern.chars().count() as u16,
+                input.y + 1,
+            ));
+        }
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::File(path) => ListItem::new(Line::from(path.display().to_string().bold())),
+                Row::Match {
+                    line_number, line, ..
+                } => ListItem::new(Line::from(vec![
+                    Span::from(format!("{:>6}: ", line_number)).dim(),
+                    Span::from(line.trim_end()),
+                ])),
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Matches "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            results,
+            &mut self.list,
+        );
+
+        let preview_text: Vec<Line> = match self.selected() {
+            Some((_, line_number)) => {
+                let first: usize = line_number.saturating_sub(1 + PREVIEW_CONTEXT);
+                self.preview_lines
+                    .iter()
+                    .enumerate()
+                    .skip(first)
+                    .take(PREVIEW_CONTEXT * 2 + 1)
+                    .map(|(index, line)| {
+                        let text: Line = Line::from(format!("{:>6}  {}", index + 1, line));
+                        match index + 1 == line_number {
+                            true => text.reversed(),
+                            false => text,
+                        }
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let preview_title: String = match &self.preview_path {
+            Some(path) => format!(" {} ", path.display()),
+            None => " Preview ".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(preview_text)
+                .block(Block::default().borders(Borders::ALL).title(preview_title)),
+            preview,
+        );
+
+        let help: &str = match self.mode {
+            Mode::Browse => "↑/↓ move  / edit pattern  Enter open in $EDITOR  q quit",
+            Mode::Edit => "type to search  Enter/Esc done",
+        };
+        frame.render_widget(Paragraph::new(help).dim(), help_bar);
+    }
+}
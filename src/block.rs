@@ -0,0 +1,91 @@
+//! Extraction of blocks of lines between a start and an end pattern, like `sed -n '/start/,/end/p'`.
+
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use crate::index::Index;
+use crate::matcher::Matcher;
+use crate::search::for_each_file;
+use crate::sink::Sink;
+use crate::stats::{CountingReader, Stats};
+use crate::types::FileTypes;
+
+/// Reports every block of lines that starts at a line matching `from` and ends at the next line after it
+/// matching `to`. A block without an end runs to the end of the input.
+/// The start and end lines are reported as matches, the lines in between as context.
+pub struct BlockSearcher<F: Matcher, T: Matcher> {
+    pub from: F,
+    pub to: T,
+}
+
+impl<F: Matcher, T: Matcher> BlockSearcher<F, T> {
+    /// Search a string
+    pub fn search_str(
+        &self,
+        path: Option<&Path>,
+        content: &str,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        self.search_reader(path, content.as_bytes(), sink)
+    }
+
+    /// Search a reader without loading it into memory
+    pub fn search_reader(
+        &self,
+        path: Option<&Path>,
+        reader: impl BufRead,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        let mut reader = CountingReader::new(reader);
+        let mut lines_matched: u64 = 0;
+        let mut in_block: bool = false;
+        let mut last_reported: Option<usize> = None;
+
+        sink.begin(path)?;
+        for (index, line) in (&mut reader).lines().enumerate() {
+            let line: String = line?;
+            let line_number: usize = index + 1;
+
+            if in_block {
+                match self.to.find(&line) {
+                    Some(found) => {
+                        sink.matched(line_number, &line, found)?;
+                        lines_matched += 1;
+                        in_block = false;
+                    }
+                    None => sink.context(line_number, &line)?,
+                }
+                last_reported = Some(line_number);
+            } else if let Some(found) = self.from.find(&line) {
+                if last_reported.is_some_and(|last| line_number > last + 1) {
+                    sink.context_break()?;
+                }
+                sink.matched(line_number, &line, found)?;
+                lines_matched += 1;
+                last_reported = Some(line_number);
+                in_block = true;
+            }
+        }
+        sink.end(path)?;
+        Ok(Stats::for_input(lines_matched, reader.count()))
+    }
+
+    /// Search every file below a directory, or only those of the selected file types when given.
+    /// Files that aren't valid UTF-8 are skipped. When an index is given, files that can't contain a
+    /// block start are skipped too.
+    pub fn search_dir(
+        &self,
+        root: &Path,
+        index: Option<&Index>,
+        types: Option<&FileTypes>,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        for_each_file(
+            root,
+            index,
+            types,
+            self.from.required_literal(),
+            |path, content| self.search_str(Some(path), content, sink),
+        )
+    }
+}
@@ -0,0 +1,137 @@
+//! Column-aware search over CSV and TSV files.
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::index::Index;
+use crate::matcher::Matcher;
+use crate::search::for_each_file;
+use crate::sink::Sink;
+use crate::stats::{CountingReader, Stats};
+use crate::types::FileTypes;
+use crate::walk::warn_skipped;
+
+/// Matches a pattern against the fields of each row of delimited input, rather than raw lines, so quoted
+/// fields with embedded delimiters or newlines are handled and matches can't span columns.
+/// The header row is reported as context before the first matching row of each input, matching rows are
+/// reported re-encoded as delimited text with their starting line number. Match offsets are within the
+/// field that matched.
+pub struct CsvSearcher<M: Matcher> {
+    pub matcher: M,
+    /// Names of the columns to search, or all columns when empty
+    pub columns: Vec<String>,
+    pub delimiter: u8,
+}
+
+impl<M: Matcher> CsvSearcher<M> {
+    /// Search a string
+    pub fn search_str(
+        &self,
+        path: Option<&Path>,
+        content: &str,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        self.search_reader(path, content.as_bytes(), sink)
+    }
+
+    /// Search a reader without loading it into memory
+    pub fn search_reader(
+        &self,
+        path: Option<&Path>,
+        reader: impl Read,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_reader(CountingReader::new(reader));
+        let mut lines_matched: u64 = 0;
+        let header: StringRecord = reader.headers()?.clone();
+        let columns: Vec<usize> = self.column_indexes(&header)?;
+        let mut header_reported: bool = false;
+
+        sink.begin(path)?;
+        for record in reader.records() {
+            let record: StringRecord = record?;
+            let found = record
+                .iter()
+                .enumerate()
+                .filter(|(column, _)| columns.is_empty() || columns.contains(column))
+                .find_map(|(_, field)| self.matcher.find(field));
+
+            if let Some(found) = found {
+                lines_matched += 1;
+                if !header_reported {
+                    sink.context(1, &self.encode(&header)?)?;
+                    header_reported = true;
+                }
+                let line_number: usize = record
+                    .position()
+                    .map_or(0, |position| position.line() as usize);
+                sink.matched(line_number, &self.encode(&record)?, found)?;
+            }
+        }
+        sink.end(path)?;
+        Ok(Stats::for_input(lines_matched, reader.get_ref().count()))
+    }
+
+    /// Search every file below a directory, or only those of the selected file types when given.
+    /// Files that aren't valid UTF-8 are skipped, and so are files the index rules out. Files
+    /// without one of the selected columns, such as a README next to the data, are skipped with a
+    /// warning. Literals containing quotes don't use the index, as quotes are doubled in raw text.
+    pub fn search_dir(
+        &self,
+        root: &Path,
+        index: Option<&Index>,
+        types: Option<&FileTypes>,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        let literal: Option<&str> = self
+            .matcher
+            .required_literal()
+            .filter(|literal| !literal.contains('"'));
+        for_each_file(root, index, types, literal, |path, content| {
+            match self.search_str(Some(path), content, sink) {
+                Err(error) if error.kind() == io::ErrorKind::InvalidInput => {
+                    warn_skipped(Some(path), &error);
+                    Ok(Stats::default())
+                }
+                result => result,
+            }
+        })
+    }
+
+    /// Positions of the selected columns in the header, failing with `InvalidInput` if one is missing
+    fn column_indexes(&self, header: &StringRecord) -> io::Result<Vec<usize>> {
+        self.columns
+            .iter()
+            .map(|name| {
+                header
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("no column named `{}`", name),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Encode a row back into delimited text, quoting fields where needed, without a trailing newline
+    fn encode(&self, record: &StringRecord) -> io::Result<String> {
+        let mut writer = WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_writer(Vec::new());
+        writer.write_record(record)?;
+        let encoded: Vec<u8> = writer
+            .into_inner()
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        let encoded: String = String::from_utf8(encoded)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(encoded.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
@@ -0,0 +1,235 @@
+//! Field search over key-value records, either one `key: value` pair per line or logfmt
+//! (`level=error msg="timed out"`) with several pairs per line.
+
+use std::fmt;
+
+use crate::matcher::{Match, Matcher};
+
+/// Comparison applied to a field's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// What a field's value has to satisfy for a line to match
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The field only has to be present
+    Exists,
+    /// The value is compared numerically when both sides are numbers, and as a string otherwise.
+    /// Ordering comparisons never match non-numeric values.
+    Compare(Op, String),
+}
+
+/// Errors from parsing a field condition
+#[derive(Debug, PartialEq)]
+pub enum FieldError {
+    EmptyKey,
+    /// A condition with something before its operator, such as a field name, or nothing after it
+    InvalidCondition(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::EmptyKey => write!(f, "field name can't be empty"),
+            FieldError::InvalidCondition(condition) => write!(
+                f,
+                "invalid condition `{}`, expected a value, or an operator and a value like `>15`",
+                condition
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl Condition {
+    /// Parse a condition such as `>15`, `<=0.5`, `!=debug`, `=error` or plain `error` (equality).
+    /// The field name is given separately, so a condition like `bar>15` is rejected rather than
+    /// comparing against `15` whatever the field.
+    pub fn parse(text: &str) -> Result<Condition, FieldError> {
+        let text: &str = text.trim();
+        match split_op(text) {
+            Some((op, "", value)) if !value.trim().is_empty() => {
+                Ok(Condition::Compare(op, value.trim().to_string()))
+            }
+            Some(_) => Err(FieldError::InvalidCondition(text.to_string())),
+            None => Ok(Condition::Compare(Op::Eq, text.to_string())),
+        }
+    }
+
+    /// Whether a value satisfies the condition
+    pub fn matches(&self, value: &str) -> bool {
+        let (op, expected) = match self {
+            Condition::Exists => return true,
+            Condition::Compare(op, expected) => (op, expected),
+        };
+
+        match (value.parse::<f64>(), expected.parse::<f64>()) {
+            (Ok(value), Ok(expected)) => match op {
+                Op::Eq => value == expected,
+                Op::Ne => value != expected,
+                Op::Lt => value < expected,
+                Op::Le => value <= expected,
+                Op::Gt => value > expected,
+                Op::Ge => value >= expected,
+            },
+            _ => match op {
+                Op::Eq => value == expected,
+                Op::Ne => value != expected,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Matches lines whose field with the given key satisfies a condition
+#[derive(Debug, Clone)]
+pub struct FieldMatcher {
+    key: String,
+    condition: Condition,
+}
+
+impl FieldMatcher {
+    pub fn new(key: &str, condition: Condition) -> Result<FieldMatcher, FieldError> {
+        let key: &str = key.trim();
+        if key.is_empty() {
+            return Err(FieldError::EmptyKey);
+        }
+        Ok(FieldMatcher {
+            key: key.to_string(),
+            condition,
+        })
+    }
+
+    /// Parse a field with an optional inline condition, such as `bar`, `bar>15` or `level=error`
+    pub fn parse(text: &str) -> Result<FieldMatcher, FieldError> {
+        match split_op(text) {
+            Some((op, key, value)) => {
+                FieldMatcher::new(key, Condition::Compare(op, value.trim().to_string()))
+            }
+            None => FieldMatcher::new(text, Condition::Exists),
+        }
+    }
+}
+
+impl Matcher for FieldMatcher {
+    /// Matching lines report the span of the field's value
+    fn find(&self, line: &str) -> Option<Match> {
+        parse_fields(line)
+            .into_iter()
+            .find(|field| field.key == self.key && self.condition.matches(field.value))
+            .map(|field| Match::exact(field.start, field.start + field.value.len()))
+    }
+}
+
+/// A key-value pair found in a line, with the byte offset of its value
+#[derive(Debug, PartialEq)]
+pub struct Field<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    pub start: usize,
+}
+
+/// Parse the fields of a line. Lines starting with a single `key:` are read as one `key: value` pair,
+/// anything else as logfmt `key=value` pairs, where double-quoted values may contain spaces.
+pub fn parse_fields(line: &str) -> Vec<Field<'_>> {
+    match line.split_once(':') {
+        Some((key, value))
+            if !key.trim().is_empty()
+                && !key.trim().contains(|c: char| c.is_whitespace() || c == '=') =>
+        {
+            let start: usize = key.len() + 1 + (value.len() - value.trim_start().len());
+            vec![Field {
+                key: key.trim(),
+                value: value.trim(),
+                start,
+            }]
+        }
+        _ => parse_logfmt(line),
+    }
+}
+
+fn parse_logfmt(line: &str) -> Vec<Field<'_>> {
+    let mut fields: Vec<Field> = Vec::new();
+    let bytes: &[u8] = line.as_bytes();
+    let mut position: usize = 0;
+
+    while position < bytes.len() {
+        if bytes[position].is_ascii_whitespace() {
+            position += 1;
+            continue;
+        }
+
+        let key_start: usize = position;
+        while position < bytes.len()
+            && !bytes[position].is_ascii_whitespace()
+            && bytes[position] != b'='
+        {
+            position += 1;
+        }
+        if position >= bytes.len() || bytes[position] != b'=' || position == key_start {
+            // A bare word, skip to the next token
+            while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            continue;
+        }
+        let key: &str = &line[key_start..position];
+        position += 1;
+
+        let (start, end): (usize, usize) = if position < bytes.len() && bytes[position] == b'"' {
+            let start: usize = position + 1;
+            let mut end: usize = start;
+            while end < bytes.len() && bytes[end] != b'"' {
+                let escaped_quote: bool = bytes[end] == b'\\' && bytes.get(end + 1) == Some(&b'"');
+                end += if escaped_quote { 2 } else { 1 };
+            }
+            position = (end + 1).min(bytes.len());
+            (start, end)
+        } else {
+            let start: usize = position;
+            while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            (start, position)
+        };
+
+        fields.push(Field {
+            key,
+            value: &line[start..end],
+            start,
+        });
+    }
+    fields
+}
+
+/// Split `key OP value` at its first comparison operator
+fn split_op(text: &str) -> Option<(Op, &str, &str)> {
+    let index: usize = text.find(['=', '!', '<', '>'])?;
+    let rest: &str = &text[index..];
+    let (op, length): (Op, usize) = if rest.starts_with(">=") {
+        (Op::Ge, 2)
+    } else if rest.starts_with("<=") {
+        (Op::Le, 2)
+    } else if rest.starts_with("!=") {
+        (Op::Ne, 2)
+    } else if rest.starts_with("==") {
+        (Op::Eq, 2)
+    } else if rest.starts_with('>') {
+        (Op::Gt, 1)
+    } else if rest.starts_with('<') {
+        (Op::Lt, 1)
+    } else if rest.starts_with('=') {
+        (Op::Eq, 1)
+    } else {
+        return None;
+    };
+    Some((op, &text[..index], &rest[length..]))
+}
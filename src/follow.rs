@@ -0,0 +1,130 @@
+//! Follow mode: keep a file open and report matching lines as they are appended, like `tail -F | grep`.
+
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::matcher::Matcher;
+use crate::sink::Sink;
+
+/// A file being followed, remembering how far it has been read and which file it refers to
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    file_id: Option<(u64, u64)>,
+    position: u64,
+    pending: Vec<u8>,
+    line_number: usize,
+}
+
+impl Follower {
+    /// Open a file for following, starting at the beginning so existing matches are reported first
+    pub fn new(path: &Path) -> io::Result<Follower> {
+        let file: File = File::open(path)?;
+        let file_id: Option<(u64, u64)> = file_id(&file.metadata()?);
+        Ok(Follower {
+            path: path.to_path_buf(),
+            file,
+            file_id,
+            position: 0,
+            pending: Vec::new(),
+            line_number: 0,
+        })
+    }
+
+    /// Read everything appended since the last poll and report the complete lines that match to the sink.
+    /// Handles truncation (start again from the top) and rotation (re-open when the path points to a new file).
+    pub fn poll(&mut self, matcher: &impl Matcher, sink: &mut impl Sink) -> io::Result<()> {
+        if self.file.metadata()?.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.pending.clear();
+            self.line_number = 0;
+        }
+
+        self.read_available(matcher, sink)?;
+
+        // The path may have been rotated away, in which case the old file has been drained above and
+        // the new one is read from the start. A missing path is retried on the next poll.
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            let current_id: Option<(u64, u64)> = file_id(&metadata);
+            if current_id.is_some() && current_id != self.file_id {
+                self.flush_pending(matcher, sink)?;
+                *self = Follower::new(&self.path)?;
+                self.read_available(matcher, sink)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read up to the current end of the file and report the complete lines that match
+    fn read_available(&mut self, matcher: &impl Matcher, sink: &mut impl Sink) -> io::Result<()> {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.position += self.file.read_to_end(&mut buffer)? as u64;
+        self.pending.extend_from_slice(&buffer);
+
+        while let Some(newline) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            self.report_line(&line[..line.len() - 1], matcher, sink)?;
+        }
+        Ok(())
+    }
+
+    /// Treat a trailing partial line as complete, used when the file it belongs to goes away
+    fn flush_pending(&mut self, matcher: &impl Matcher, sink: &mut impl Sink) -> io::Result<()> {
+        let line: Vec<u8> = std::mem::take(&mut self.pending);
+        if line.is_empty() {
+            return Ok(());
+        }
+        self.report_line(&line, matcher, sink)
+    }
+
+    /// Report a single line, without its line ending, if it matches
+    fn report_line(
+        &mut self,
+        line: &[u8],
+        matcher: &impl Matcher,
+        sink: &mut impl Sink,
+    ) -> io::Result<()> {
+        self.line_number += 1;
+        let line = String::from_utf8_lossy(line);
+        let line: &str = line.strip_suffix('\r').unwrap_or(&line);
+        if let Some(found) = matcher.find(line) {
+            sink.matched(self.line_number, line, found)?;
+        }
+        Ok(())
+    }
+}
+
+/// Follow a file forever, checking for new data every `interval`.
+/// The file is one input to the sink, which is flushed after every poll so output appears as it arrives.
+pub fn follow(
+    path: &Path,
+    matcher: &impl Matcher,
+    sink: &mut impl Sink,
+    interval: Duration,
+) -> io::Result<()> {
+    let mut follower: Follower = Follower::new(path)?;
+    sink.begin(Some(path))?;
+    loop {
+        follower.poll(matcher, sink)?;
+        sink.flush()?;
+        thread::sleep(interval);
+    }
+}
+
+/// Identify the file behind a path so rotation can be detected
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Identify the file behind a path so rotation can be detected
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,255 @@
+//! Search over git history, reading blobs and commits from the repository's object store instead of the
+//! work tree.
+
+use chrono::{DateTime, FixedOffset, TimeZone};
+use git2::{
+    Commit, Diff, DiffLine, DiffOptions, ObjectType, Oid, Repository, Sort, Tree, TreeWalkMode,
+    TreeWalkResult,
+};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::matcher::{Match, Matcher};
+use crate::search::Searcher;
+use crate::sink::Sink;
+use crate::stats::Stats;
+
+/// Errors from searching a repository
+#[derive(Debug)]
+pub enum HistoryError {
+    Git(git2::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Git(error) => write!(f, "{}", error.message()),
+            HistoryError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+impl From<git2::Error> for HistoryError {
+    fn from(error: git2::Error) -> HistoryError {
+        HistoryError::Git(error)
+    }
+}
+
+impl From<io::Error> for HistoryError {
+    fn from(error: io::Error) -> HistoryError {
+        HistoryError::Io(error)
+    }
+}
+
+/// A commit whose changes added or removed lines matching the pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitMatch {
+    /// Full hexadecimal commit id
+    pub id: String,
+    pub author: String,
+    pub time: DateTime<FixedOffset>,
+    /// First line of the commit message
+    pub summary: String,
+    pub changes: Vec<LineChange>,
+}
+
+/// A matching line added or removed by a commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineChange {
+    pub path: PathBuf,
+    pub added: bool,
+    /// Line number in the new file for added lines, and in the old file for removed lines
+    pub line_number: usize,
+    pub line: String,
+    pub found: Match,
+}
+
+/// A git repository, restricted to the file or directory it was opened with
+pub struct History {
+    repository: Repository,
+    /// The selected path relative to the root of the repository, empty for the whole repository
+    prefix: PathBuf,
+}
+
+impl History {
+    /// Open the repository containing `path`, so searches only cover `path` within it
+    pub fn open(path: &Path) -> Result<History, HistoryError> {
+        let repository: Repository = Repository::discover(path)?;
+        let prefix: PathBuf = match repository.workdir() {
+            Some(workdir) => path
+                .canonicalize()?
+                .strip_prefix(workdir.canonicalize()?)
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            None => PathBuf::new(),
+        };
+        Ok(History { repository, prefix })
+    }
+
+    /// Search the files as they were at a revision such as `HEAD~10`, a tag or a commit id.
+    /// Paths are reported as `REV:path`, like `git grep` does. Binary files and files that aren't valid
+    /// UTF-8 are skipped.
+    pub fn search_rev(
+        &self,
+        rev: &str,
+        searcher: &Searcher,
+        matcher: &impl Matcher,
+        sink: &mut impl Sink,
+    ) -> Result<Stats, HistoryError> {
+        let tree: Tree = self.repository.revparse_single(rev)?.peel_to_tree()?;
+        let mut stats: Stats = Stats::default();
+
+        for (path, id) in self.blobs(&tree)? {
+            let blob = self.repository.find_blob(id)?;
+            if blob.is_binary() {
+                continue;
+            }
+            let content: &str = match std::str::from_utf8(blob.content()) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let path: PathBuf = PathBuf::from(format!("{}:{}", rev, path.display()));
+            stats += searcher.search_str(matcher, Some(&path), content, sink)?;
+        }
+        Ok(stats)
+    }
+
+    /// Walk the history reachable from a revision, newest first, and report every commit whose diff
+    /// against its parent adds or removes lines matching the pattern, similar to `git log -S`.
+    /// Merge commits are skipped, and so are binary files and lines that aren't valid UTF-8.
+    pub fn search_log(
+        &self,
+        rev: &str,
+        matcher: &impl Matcher,
+        mut report: impl FnMut(&CommitMatch) -> io::Result<()>,
+    ) -> Result<(), HistoryError> {
+        let start: Commit = self.repository.revparse_single(rev)?.peel_to_commit()?;
+        let mut walk = self.repository.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        walk.push(start.id())?;
+
+        for id in walk {
+            let commit: Commit = self.repository.find_commit(id?)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let changes: Vec<LineChange> = self.changes(&commit, matcher)?;
+            if changes.is_empty() {
+                continue;
+            }
+
+            let time = commit.time();
+            let offset: FixedOffset = FixedOffset::east_opt(time.offset_minutes() * 60)
+                .unwrap_or(FixedOffset::east_opt(0).unwrap());
+            let author = commit.author();
+            report(&CommitMatch {
+                id: commit.id().to_string(),
+                author: format!(
+                    "{} <{}>",
+                    String::from_utf8_lossy(author.name_bytes()),
+                    String::from_utf8_lossy(author.email_bytes())
+                ),
+                time: offset
+                    .timestamp_opt(time.seconds(), 0)
+                    .single()
+                    .unwrap_or_default(),
+                summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+                    .into_owned(),
+                changes,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Paths and ids of the blobs in a tree below the selected path, sorted by path
+    fn blobs(&self, tree: &Tree) -> Result<Vec<(PathBuf, Oid)>, HistoryError> {
+        if self.prefix.as_os_str().is_empty() {
+            return Ok(walk_tree(tree, Path::new("")));
+        }
+
+        let entry = tree.get_path(&self.prefix)?;
+        match entry.kind() {
+            Some(ObjectType::Blob) => Ok(vec![(self.prefix.clone(), entry.id())]),
+            Some(ObjectType::Tree) => {
+                let subtree: Tree = self.repository.find_tree(entry.id())?;
+                Ok(walk_tree(&subtree, &self.prefix))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Matching lines added or removed by a commit, compared to its parent or to an empty tree for the
+    /// first commit
+    fn changes(
+        &self,
+        commit: &Commit,
+        matcher: &impl Matcher,
+    ) -> Result<Vec<LineChange>, HistoryError> {
+        let parent_tree: Option<Tree> = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let mut options = DiffOptions::new();
+        options.context_lines(0);
+        if !self.prefix.as_os_str().is_empty() {
+            options.pathspec(&self.prefix);
+        }
+        let diff: Diff = self.repository.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?;
+
+        let mut changes: Vec<LineChange> = Vec::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line: DiffLine| {
+                let (added, line_number): (bool, Option<u32>) = match line.origin() {
+                    '+' => (true, line.new_lineno()),
+                    '-' => (false, line.old_lineno()),
+                    _ => return true,
+                };
+                let text: &str = match std::str::from_utf8(line.content()) {
+                    Ok(text) => text.trim_end_matches(['\r', '\n']),
+                    Err(_) => return true,
+                };
+                let file = match added {
+                    true => delta.new_file(),
+                    false => delta.old_file(),
+                };
+                if let Some(found) = matcher.find(text) {
+                    changes.push(LineChange {
+                        path: file.path().map(Path::to_path_buf).unwrap_or_default(),
+                        added,
+                        line_number: line_number.unwrap_or_default() as usize,
+                        line: text.to_string(),
+                        found,
+                    });
+                }
+                true
+            }),
+        )?;
+        Ok(changes)
+    }
+}
+
+/// Paths and ids of every blob in a tree, sorted by path, with paths starting at `base`
+fn walk_tree(tree: &Tree, base: &Path) -> Vec<(PathBuf, Oid)> {
+    let mut blobs: Vec<(PathBuf, Oid)> = Vec::new();
+    // Walking only fails when the callback aborts, which this one never does
+    let _ = tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            let name = String::from_utf8_lossy(entry.name_bytes());
+            blobs.push((base.join(dir).join(name.as_ref()), entry.id()));
+        }
+        TreeWalkResult::Ok
+    });
+    blobs.sort();
+    blobs
+}
@@ -0,0 +1,228 @@
+//! Persistent trigram index used to narrow down which files need to be searched.
+//!
+//! The index maps every file below a directory to the set of byte trigrams it contains, along with the
+//! modification time and size it had when indexed. A file can only contain a literal pattern if it contains
+//! all of the pattern's trigrams, so fresh entries missing any of them are skipped. Files that are missing
+//! from the index or have changed since it was built are always searched, so results never differ from an
+//! unindexed search.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::walk::{walk_files, warn_skipped};
+
+/// Name of the index file created at the root of an indexed directory
+pub const INDEX_FILE_NAME: &str = ".grrs-index";
+
+const MAGIC: &[u8; 8] = b"GRRSIDX1";
+
+/// Trigram index for all files below a directory
+#[derive(Debug, Default)]
+pub struct Index {
+    entries: BTreeMap<PathBuf, IndexEntry>,
+}
+
+/// Index data for a single file, keyed by its path relative to the indexed directory
+#[derive(Debug, PartialEq)]
+struct IndexEntry {
+    modified: (u64, u32),
+    size: u64,
+    trigrams: Vec<u32>,
+}
+
+/// Counts reported after building or updating an index
+#[derive(Debug, Default, PartialEq)]
+pub struct BuildSummary {
+    pub files: usize,
+    pub updated: usize,
+}
+
+impl Index {
+    /// Location of the index file for a directory
+    pub fn path_for(root: &Path) -> PathBuf {
+        root.join(INDEX_FILE_NAME)
+    }
+
+    /// Build or incrementally update the index for a directory and save it.
+    /// Files whose modification time and size are unchanged keep their existing entry. Files that can't be
+    /// read are left out with a warning, and so are always searched.
+    pub fn build(root: &Path) -> io::Result<BuildSummary> {
+        let previous: Index = match Index::load(root) {
+            Ok(index) => index,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Index::default(),
+            Err(error) => return Err(error),
+        };
+        let (index, summary) = Index::update(root, previous)?;
+        index.save(root)?;
+        Ok(summary)
+    }
+
+    fn update(root: &Path, mut previous: Index) -> io::Result<(Index, BuildSummary)> {
+        let mut index: Index = Index::default();
+        let mut summary: BuildSummary = BuildSummary::default();
+
+        for path in walk_files(root)? {
+            let relative: PathBuf = match path.strip_prefix(root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => continue,
+            };
+            // Paths that can't be stored are left out and therefore always searched
+            if relative.to_str().is_none() {
+                continue;
+            }
+
+            let (modified, size) =
+                match fs::metadata(&path).and_then(|metadata| file_stamp(&metadata)) {
+                    Ok(stamp) => stamp,
+                    Err(error) => {
+                        warn_skipped(Some(&path), &error);
+                        continue;
+                    }
+                };
+            let entry: IndexEntry = match previous.entries.remove(&relative) {
+                Some(entry) if entry.modified == modified && entry.size == size => entry,
+                _ => match fs::read(&path) {
+                    Ok(content) => {
+                        summary.updated += 1;
+                        IndexEntry {
+                            modified,
+                            size,
+                            trigrams: trigrams(&content),
+                        }
+                    }
+                    Err(error) => {
+                        warn_skipped(Some(&path), &error);
+                        continue;
+                    }
+                },
+            };
+            index.entries.insert(relative, entry);
+            summary.files += 1;
+        }
+
+        Ok((index, summary))
+    }
+
+    /// Load the index saved for a directory
+    pub fn load(root: &Path) -> io::Result<Index> {
+        let mut reader = BufReader::new(File::open(Index::path_for(root))?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a grrs index file",
+            ));
+        }
+
+        let mut index: Index = Index::default();
+        for _ in 0..read_u64(&mut reader)? {
+            let mut path = vec![0u8; read_u32(&mut reader)? as usize];
+            reader.read_exact(&mut path)?;
+            let path: String = String::from_utf8(path)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+            let modified: (u64, u32) = (read_u64(&mut reader)?, read_u32(&mut reader)?);
+            let size: u64 = read_u64(&mut reader)?;
+            let count: u32 = read_u32(&mut reader)?;
+            let mut trigrams: Vec<u32> = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                trigrams.push(read_u32(&mut reader)?);
+            }
+
+            index.entries.insert(
+                PathBuf::from(path),
+                IndexEntry {
+                    modified,
+                    size,
+                    trigrams,
+                },
+            );
+        }
+        Ok(index)
+    }
+
+    /// Save the index into its directory
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(Index::path_for(root))?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for (path, entry) in &self.entries {
+            let path: &[u8] = path.to_str().unwrap_or_default().as_bytes();
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path)?;
+            writer.write_all(&entry.modified.0.to_le_bytes())?;
+            writer.write_all(&entry.modified.1.to_le_bytes())?;
+            writer.write_all(&entry.size.to_le_bytes())?;
+            writer.write_all(&(entry.trigrams.len() as u32).to_le_bytes())?;
+            for trigram in &entry.trigrams {
+                writer.write_all(&trigram.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Decide whether a file below `root` could contain a literal pattern.
+    /// Returns `true` unless the file has an up to date entry that lacks one of the pattern's trigrams.
+    pub fn may_contain(&self, root: &Path, path: &Path, pattern: &str) -> bool {
+        let entry: &IndexEntry = match path
+            .strip_prefix(root)
+            .ok()
+            .and_then(|relative| self.entries.get(relative))
+        {
+            Some(entry) => entry,
+            None => return true,
+        };
+
+        let is_fresh: bool = match fs::metadata(path).and_then(|metadata| file_stamp(&metadata)) {
+            Ok((modified, size)) => modified == entry.modified && size == entry.size,
+            Err(_) => false,
+        };
+        if !is_fresh {
+            return true;
+        }
+
+        trigrams(pattern.as_bytes())
+            .iter()
+            .all(|trigram| entry.trigrams.binary_search(trigram).is_ok())
+    }
+}
+
+/// Sorted, de-duplicated byte trigrams of some content
+fn trigrams(content: &[u8]) -> Vec<u32> {
+    let mut trigrams: Vec<u32> = content
+        .windows(3)
+        .map(|window| (window[0] as u32) << 16 | (window[1] as u32) << 8 | window[2] as u32)
+        .collect();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+/// Modification time and size used to tell whether an entry is out of date
+fn file_stamp(metadata: &Metadata) -> io::Result<((u64, u32), u64)> {
+    let modified = metadata
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok((
+        (modified.as_secs(), modified.subsec_nanos()),
+        metadata.len(),
+    ))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
@@ -0,0 +1,230 @@
+//! Search over NDJSON, matching the pattern against one field of each JSON record.
+
+use serde_json::Value;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use crate::matcher::Matcher;
+use crate::search::for_each_file;
+use crate::sink::Sink;
+use crate::stats::{CountingReader, Stats};
+use crate::types::FileTypes;
+
+/// A path into a JSON value, such as `$.request.status` or `$.items[0]['user id']`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Errors from parsing a JSON path
+#[derive(Debug, PartialEq)]
+pub struct JsonPathError(String);
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSON path: {}", self.0)
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+impl JsonPath {
+    /// Parse a path made of `.key`, `['key']` and `[index]` segments, optionally starting with `$`
+    pub fn parse(text: &str) -> Result<JsonPath, JsonPathError> {
+        let mut segments: Vec<Segment> = Vec::new();
+        let rest: &str = text.trim().strip_prefix('$').unwrap_or(text.trim());
+        let mut chars = rest.chars().peekable();
+        // A path without `$` may start with a bare key
+        let mut expect_key: bool = !rest.is_empty() && !rest.starts_with(['.', '[']);
+
+        while expect_key || chars.peek().is_some() {
+            if !expect_key {
+                match chars.next() {
+                    Some('.') => (),
+                    Some('[') => {
+                        let mut inside: String = String::new();
+                        loop {
+                            match chars.next() {
+                                Some(']') if !is_open_quote(&inside) => break,
+                                Some(c) => inside.push(c),
+                                None => {
+                                    return Err(JsonPathError(format!(
+                                        "unclosed `[` in `{}`",
+                                        text
+                                    )))
+                                }
+                            }
+                        }
+                        segments.push(parse_bracket(&inside).ok_or_else(|| {
+                            JsonPathError(format!("bad segment `[{}]` in `{}`", inside, text))
+                        })?);
+                        continue;
+                    }
+                    Some(c) => {
+                        return Err(JsonPathError(format!("unexpected `{}` in `{}`", c, text)))
+                    }
+                    None => break,
+                }
+            }
+            expect_key = false;
+
+            let mut key: String = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '.' || c == '[' {
+                    break;
+                }
+                key.push(c);
+                chars.next();
+            }
+            if key.is_empty() {
+                return Err(JsonPathError(format!("empty key in `{}`", text)));
+            }
+            segments.push(Segment::Key(key));
+        }
+        Ok(JsonPath { segments })
+    }
+
+    /// Look up the value at this path
+    pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value.get(index),
+            })
+    }
+}
+
+/// Whether the text inside brackets is a quoted key that hasn't been closed yet
+fn is_open_quote(inside: &str) -> bool {
+    let quote: char = match inside.chars().next() {
+        Some(quote @ ('\'' | '"')) => quote,
+        _ => return false,
+    };
+    inside.len() == 1 || !inside.ends_with(quote)
+}
+
+fn parse_bracket(inside: &str) -> Option<Segment> {
+    let inside: &str = inside.trim();
+    for quote in ['\'', '"'] {
+        if let Some(key) = inside
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Some(Segment::Key(key.to_string()));
+        }
+    }
+    inside.parse().ok().map(Segment::Index)
+}
+
+/// What to do with lines that aren't valid JSON
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum InvalidJson {
+    /// Ignore them
+    Skip,
+    /// Print a warning with the line number to stderr and carry on
+    #[default]
+    Report,
+    /// Stop with an error
+    Fail,
+}
+
+/// Matches a pattern against the value at a JSON path in every line of NDJSON input.
+/// Strings are matched without their quotes, other values as compact JSON. Match offsets reported to the
+/// sink are always within the extracted value, even when the whole record is printed.
+pub struct JsonSearcher<M: Matcher> {
+    pub path: JsonPath,
+    pub matcher: M,
+    /// Report the extracted value instead of the whole record
+    pub print_value: bool,
+    pub invalid: InvalidJson,
+}
+
+impl<M: Matcher> JsonSearcher<M> {
+    /// Search a string
+    pub fn search_str(
+        &self,
+        path: Option<&Path>,
+        content: &str,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        self.search_reader(path, content.as_bytes(), sink)
+    }
+
+    /// Search a reader without loading it into memory
+    pub fn search_reader(
+        &self,
+        path: Option<&Path>,
+        reader: impl BufRead,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        let mut reader = CountingReader::new(reader);
+        let mut lines_matched: u64 = 0;
+
+        sink.begin(path)?;
+        for (index, line) in (&mut reader).lines().enumerate() {
+            let line: String = line?;
+            let line_number: usize = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: Value = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(error) => {
+                    let location: String = match path {
+                        Some(path) => format!("{}:{}", path.display(), line_number),
+                        None => format!("line {}", line_number),
+                    };
+                    match self.invalid {
+                        InvalidJson::Skip => (),
+                        InvalidJson::Report => eprintln!("{}: invalid JSON: {}", location, error),
+                        InvalidJson::Fail => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("{}: invalid JSON: {}", location, error),
+                            ))
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            let value: String = match self.path.select(&record) {
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+                None => continue,
+            };
+            if let Some(found) = self.matcher.find(&value) {
+                lines_matched += 1;
+                match self.print_value {
+                    true => sink.matched(line_number, &value, found)?,
+                    false => sink.matched(line_number, &line, found)?,
+                }
+            }
+        }
+        sink.end(path)?;
+        Ok(Stats::for_input(lines_matched, reader.count()))
+    }
+
+    /// Search every file below a directory, or only those of the selected file types when given.
+    /// Files that aren't valid UTF-8 are skipped. The index isn't used to skip files, since escapes can
+    /// make a value differ from its raw JSON text.
+    pub fn search_dir(
+        &self,
+        root: &Path,
+        types: Option<&FileTypes>,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        for_each_file(root, None, types, None, |path, content| {
+            self.search_str(Some(path), content, sink)
+        })
+    }
+}
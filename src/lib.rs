@@ -1,11 +1,65 @@
-/// Function to find matches in a string and write them to a writer, and print any errors that occurred.
-pub fn find_matches(content: &str, pattern: &str, mut writer: impl std::io::Write) {
-    for line in content.lines() {
-        if line.contains(pattern) {
-            match writeln!(writer, "{}", line) {
-                Ok(_) => (),
-                Err(e) => println!("Error: {}", e),
-            }
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+pub mod block;
+pub mod delimited;
+pub mod field;
+pub mod follow;
+#[cfg(feature = "git")]
+pub mod history;
+pub mod index;
+pub mod json;
+pub mod matcher;
+pub mod query;
+pub mod search;
+pub mod secrets;
+pub mod sink;
+pub mod stats;
+pub mod types;
+pub mod walk;
+
+use index::Index;
+use matcher::SubstringMatcher;
+use search::Searcher;
+use sink::StandardSink;
+use stats::Stats;
+
+/// Function to find matches in a string and write them to a writer, returning the search stats, and print
+/// any errors that occurred.
+pub fn find_matches(content: &str, pattern: &str, writer: impl std::io::Write) -> Stats {
+    let matcher = SubstringMatcher::new(pattern);
+    let mut sink = StandardSink::new(writer);
+    match Searcher::default().search_str(&matcher, None, content, &mut sink) {
+        Ok(stats) => stats,
+        Err(e) => {
+            println!("Error: {}", e);
+            Stats::default()
         }
     }
 }
+
+/// Function to find matches in a reader line by line, without loading the whole input into memory,
+/// and write them to a writer. Returns the search stats, or any error from reading or writing.
+pub fn find_matches_in_reader(
+    reader: impl BufRead,
+    pattern: &str,
+    writer: impl Write,
+) -> io::Result<Stats> {
+    let matcher = SubstringMatcher::new(pattern);
+    let mut sink = StandardSink::new(writer);
+    Searcher::default().search_reader(&matcher, None, reader, &mut sink)
+}
+
+/// Function to find matches in every file below a directory and write them to a writer, prefixed by
+/// the path of the file they were found in. Files that aren't valid UTF-8 are skipped. When an index
+/// is given, files it rules out are not read at all. Returns the search stats summed over all files.
+pub fn find_matches_in_dir(
+    root: &Path,
+    pattern: &str,
+    index: Option<&Index>,
+    writer: impl Write,
+) -> io::Result<Stats> {
+    let matcher = SubstringMatcher::new(pattern);
+    let mut sink = StandardSink::new(writer).with_path(true);
+    Searcher::default().search_dir(&matcher, root, index, None, &mut sink)
+}
@@ -0,0 +1,359 @@
+//! Matching strategies used to decide whether a line matches and where.
+
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+
+/// Location of a match within a line, as byte offsets, along with how many edits it took to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub distance: usize,
+}
+
+impl Match {
+    /// An exact match spanning the given byte offsets
+    pub fn exact(start: usize, end: usize) -> Match {
+        Match {
+            start,
+            end,
+            distance: 0,
+        }
+    }
+}
+
+/// A strategy for finding matches within a single line
+pub trait Matcher {
+    /// Find the first match in a line, if any
+    fn find(&self, line: &str) -> Option<Match>;
+
+    /// Whether a line contains a match
+    fn is_match(&self, line: &str) -> bool {
+        self.find(line).is_some()
+    }
+
+    /// A literal that every match is guaranteed to contain, used to narrow down files with the index
+    fn required_literal(&self) -> Option<&str> {
+        None
+    }
+
+    /// Called with the whole content of an input before its lines are searched. Matchers that depend on
+    /// the input as a whole return a version bound to it, the rest keep the default of `None`.
+    fn bind(&self, _content: &str) -> Option<Box<dyn Matcher + '_>> {
+        None
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for &M {
+    fn find(&self, line: &str) -> Option<Match> {
+        (**self).find(line)
+    }
+
+    fn required_literal(&self) -> Option<&str> {
+        (**self).required_literal()
+    }
+
+    fn bind(&self, content: &str) -> Option<Box<dyn Matcher + '_>> {
+        (**self).bind(content)
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Box<M> {
+    fn find(&self, line: &str) -> Option<Match> {
+        (**self).find(line)
+    }
+
+    fn required_literal(&self) -> Option<&str> {
+        (**self).required_literal()
+    }
+
+    fn bind(&self, content: &str) -> Option<Box<dyn Matcher + '_>> {
+        (**self).bind(content)
+    }
+}
+
+/// Errors from building a matcher out of a pattern
+#[derive(Debug)]
+pub enum MatcherError {
+    Regex(regex::Error),
+    PatternTooLong(usize),
+}
+
+impl fmt::Display for MatcherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatcherError::Regex(error) => write!(f, "invalid regex: {}", error),
+            MatcherError::PatternTooLong(length) => write!(
+                f,
+                "pattern is {} characters long, fuzzy matching supports at most {}",
+                length,
+                FuzzyMatcher::MAX_PATTERN_LENGTH
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MatcherError {}
+
+/// Matches lines containing a fixed string
+#[derive(Debug, Clone)]
+pub struct SubstringMatcher {
+    pattern: String,
+}
+
+impl SubstringMatcher {
+    pub fn new(pattern: &str) -> SubstringMatcher {
+        SubstringMatcher {
+            pattern: pattern.to_string(),
+        }
+    }
+}
+
+impl Matcher for SubstringMatcher {
+    fn find(&self, line: &str) -> Option<Match> {
+        line.find(&self.pattern)
+            .map(|start| Match::exact(start, start + self.pattern.len()))
+    }
+
+    fn required_literal(&self) -> Option<&str> {
+        Some(&self.pattern)
+    }
+}
+
+/// Matches lines against a regular expression
+#[derive(Debug, Clone)]
+pub struct RegexMatcher {
+    regex: Regex,
+}
+
+impl RegexMatcher {
+    pub fn new(pattern: &str) -> Result<RegexMatcher, MatcherError> {
+        RegexMatcher::with_case_insensitive(pattern, false)
+    }
+
+    /// Build a matcher for a regular expression that optionally ignores case
+    pub fn with_case_insensitive(
+        pattern: &str,
+        case_insensitive: bool,
+    ) -> Result<RegexMatcher, MatcherError> {
+        Ok(RegexMatcher {
+            regex: RegexBuilder::new(pattern)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(MatcherError::Regex)?,
+        })
+    }
+
+    /// Build a matcher for a fixed string that ignores case
+    pub fn literal_case_insensitive(pattern: &str) -> RegexMatcher {
+        RegexMatcher::with_case_insensitive(&regex::escape(pattern), true)
+            .expect("an escaped literal is a valid regex")
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn find(&self, line: &str) -> Option<Match> {
+        self.regex
+            .find(line)
+            .map(|found| Match::exact(found.start(), found.end()))
+    }
+}
+
+/// Matches lines containing any one of several fixed strings, reporting the leftmost
+#[derive(Debug, Clone)]
+pub struct MultiLiteralMatcher {
+    patterns: Vec<String>,
+}
+
+impl MultiLiteralMatcher {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> MultiLiteralMatcher {
+        MultiLiteralMatcher {
+            patterns: patterns
+                .iter()
+                .map(|pattern| pattern.as_ref().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Matcher for MultiLiteralMatcher {
+    fn find(&self, line: &str) -> Option<Match> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| {
+                line.find(pattern.as_str())
+                    .map(|start| Match::exact(start, start + pattern.len()))
+            })
+            .min_by_key(|found| (found.start, usize::MAX - found.end))
+    }
+}
+
+/// Matches lines containing the pattern within a maximum Levenshtein distance, using the bitap algorithm.
+/// The closest match in a line is reported, not just the first one within the distance.
+#[derive(Debug, Clone)]
+pub struct FuzzyMatcher {
+    pattern: Vec<char>,
+    max_distance: usize,
+    case_insensitive: bool,
+}
+
+impl FuzzyMatcher {
+    /// Longest pattern supported, one bit of state per pattern character
+    pub const MAX_PATTERN_LENGTH: usize = 64;
+
+    pub fn new(pattern: &str, max_distance: usize) -> Result<FuzzyMatcher, MatcherError> {
+        FuzzyMatcher::with_case_insensitive(pattern, max_distance, false)
+    }
+
+    /// Build a fuzzy matcher that optionally ignores case, so differences in case don't count as edits
+    pub fn with_case_insensitive(
+        pattern: &str,
+        max_distance: usize,
+        case_insensitive: bool,
+    ) -> Result<FuzzyMatcher, MatcherError> {
+        let pattern: Vec<char> = pattern
+            .chars()
+            .map(|c| match case_insensitive {
+                true => fold_case(c),
+                false => c,
+            })
+            .collect();
+        if pattern.len() > FuzzyMatcher::MAX_PATTERN_LENGTH {
+            return Err(MatcherError::PatternTooLong(pattern.len()));
+        }
+        Ok(FuzzyMatcher {
+            pattern,
+            max_distance,
+            case_insensitive,
+        })
+    }
+
+    /// Bit mask of the pattern positions holding a character
+    fn char_mask(&self, c: char) -> u64 {
+        self.pattern
+            .iter()
+            .enumerate()
+            .filter(|(_, &p)| p == c)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// Find the best start for a match ending at `end`, trying the starts its edit distance allows
+    fn best_start(&self, chars: &[char], end: usize, distance: usize) -> (usize, usize) {
+        let length: usize = self.pattern.len();
+        let earliest: usize = end.saturating_sub(length + distance);
+        let latest: usize = (end + distance).saturating_sub(length).min(end);
+        (earliest..=latest)
+            .map(|start| (start, edit_distance(&chars[start..end], &self.pattern)))
+            .min_by_key(|&(start, distance)| (distance, start))
+            .unwrap_or((end, distance))
+    }
+}
+
+impl Matcher for FuzzyMatcher {
+    fn find(&self, line: &str) -> Option<Match> {
+        let length: usize = self.pattern.len();
+        if length <= self.max_distance {
+            return Some(Match {
+                start: 0,
+                end: 0,
+                distance: length,
+            });
+        }
+
+        let accept: u64 = 1 << (length - 1);
+        // states[d] has bit i set when the first i + 1 pattern characters match with at most d edits
+        let mut states: Vec<u64> = (0..=self.max_distance).map(|d| (1 << d) - 1).collect();
+        let chars: Vec<char> = match self.case_insensitive {
+            true => line.chars().map(fold_case).collect(),
+            false => line.chars().collect(),
+        };
+
+        // The closest match so far as (start, end, distance), replaced only by a strictly closer one so
+        // the leftmost of equally close matches wins
+        let mut best: Option<(usize, usize, usize)> = None;
+        for (position, &c) in chars.iter().enumerate() {
+            let mask: u64 = self.char_mask(c);
+            let mut previous_old: u64 = states[0];
+            states[0] = ((states[0] << 1) | 1) & mask;
+            for d in 1..=self.max_distance {
+                let old: u64 = states[d];
+                states[d] = (((old << 1) | 1) & mask)
+                    | ((previous_old << 1) | 1)
+                    | ((states[d - 1] << 1) | 1)
+                    | previous_old;
+                previous_old = old;
+            }
+
+            let distance: usize = match states.iter().position(|state| state & accept != 0) {
+                Some(distance) if best.is_none_or(|(_, _, closest)| distance < closest) => distance,
+                _ => continue,
+            };
+            let end: usize = position + 1;
+            let (start, distance) = self.best_start(&chars, end, distance);
+            best = Some((start, end, distance));
+            if distance == 0 {
+                break;
+            }
+        }
+
+        let offset = |index: usize| -> usize {
+            line.char_indices()
+                .nth(index)
+                .map_or(line.len(), |(offset, _)| offset)
+        };
+        best.map(|(start, end, distance)| Match {
+            start: offset(start),
+            end: offset(end),
+            distance,
+        })
+    }
+}
+
+/// Levenshtein distance between two character sequences
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut diagonal: usize = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above: usize = row[j + 1];
+            row[j + 1] = (diagonal + (ca != cb) as usize)
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Map a character to its lowercase form for case-insensitive comparisons, keeping characters whose
+/// lowercase form is more than one character as they are so byte offsets stay meaningful
+pub(crate) fn fold_case(c: char) -> char {
+    let mut lowercase = c.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// Find the leftmost occurrence of `needle` in `haystack` regardless of case, as byte offsets into
+/// `haystack`
+pub(crate) fn find_case_insensitive(haystack: &str, needle: &str) -> Option<Match> {
+    let needle: Vec<char> = needle.chars().map(fold_case).collect();
+    if needle.is_empty() {
+        return Some(Match::exact(0, 0));
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut rest = haystack[start..].char_indices();
+        for &expected in &needle {
+            match rest.next() {
+                Some((_, c)) if fold_case(c) == expected => (),
+                _ => return None,
+            }
+        }
+        let end: usize = rest
+            .next()
+            .map_or(haystack.len(), |(offset, _)| start + offset);
+        Some(Match::exact(start, end))
+    })
+}
@@ -0,0 +1,311 @@
+//! Boolean query language combining fixed-string terms with `AND`, `OR` and `NOT`.
+//!
+//! Terms are bare words or double-quoted strings and are tested against the line being searched.
+//! `FILE(...)` wraps a sub-query that is tested against the whole file instead, so
+//! `FILE(retry AND NOT test) AND timeout` finds lines containing `timeout` in files that contain `retry`
+//! but not `test`. `NOT` binds tightest, then `AND`, then `OR`; parentheses group. Terms are case
+//! sensitive unless the query is made case insensitive with `Query::case_insensitive`.
+
+use std::fmt;
+
+use crate::matcher::{find_case_insensitive, Match, Matcher};
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(String),
+    /// A term matched regardless of case, see `Query::case_insensitive`
+    CaseInsensitiveTerm(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    File(Box<Query>),
+    /// A sub-query already evaluated, used for `FILE(...)` once the file is known
+    Constant(bool),
+}
+
+/// Errors from parsing a query
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnterminatedString,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnexpectedEnd => write!(f, "query ended unexpectedly"),
+            QueryError::UnexpectedToken(token) => write!(f, "unexpected `{}` in query", token),
+            QueryError::UnterminatedString => write!(f, "unterminated string in query"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    File,
+    Open,
+    Close,
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::File => write!(f, "FILE"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Word(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query from its text form
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens: Vec<Token> = tokenize(text)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let query: Query = parser.parse_or()?;
+        match parser.next() {
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Ok(query),
+        }
+    }
+
+    /// Evaluate the query against a line, testing `FILE(...)` sub-queries against the line too
+    pub fn matches_line(&self, line: &str) -> bool {
+        match self {
+            Query::Term(term) => line.contains(term.as_str()),
+            Query::CaseInsensitiveTerm(term) => find_case_insensitive(line, term).is_some(),
+            Query::Not(query) => !query.matches_line(line),
+            Query::And(left, right) => left.matches_line(line) && right.matches_line(line),
+            Query::Or(left, right) => left.matches_line(line) || right.matches_line(line),
+            Query::File(query) => query.matches_line(line),
+            Query::Constant(value) => *value,
+        }
+    }
+
+    /// Resolve every `FILE(...)` sub-query against the content of a file
+    pub fn bind_file(&self, content: &str) -> Query {
+        match self {
+            Query::Term(_) | Query::CaseInsensitiveTerm(_) | Query::Constant(_) => self.clone(),
+            Query::Not(query) => Query::Not(Box::new(query.bind_file(content))),
+            Query::And(left, right) => Query::And(
+                Box::new(left.bind_file(content)),
+                Box::new(right.bind_file(content)),
+            ),
+            Query::Or(left, right) => Query::Or(
+                Box::new(left.bind_file(content)),
+                Box::new(right.bind_file(content)),
+            ),
+            Query::File(query) => Query::Constant(query.matches_line(content)),
+        }
+    }
+
+    /// The same query with every term matched regardless of case
+    pub fn case_insensitive(self) -> Query {
+        match self {
+            Query::Term(term) => Query::CaseInsensitiveTerm(term),
+            Query::Not(query) => Query::Not(Box::new(query.case_insensitive())),
+            Query::And(left, right) => Query::And(
+                Box::new(left.case_insensitive()),
+                Box::new(right.case_insensitive()),
+            ),
+            Query::Or(left, right) => Query::Or(
+                Box::new(left.case_insensitive()),
+                Box::new(right.case_insensitive()),
+            ),
+            Query::File(query) => Query::File(Box::new(query.case_insensitive())),
+            Query::CaseInsensitiveTerm(_) | Query::Constant(_) => self,
+        }
+    }
+
+    /// Whether any term contains an uppercase letter, for smart case. Operators don't count.
+    pub fn has_uppercase_term(&self) -> bool {
+        match self {
+            Query::Term(term) | Query::CaseInsensitiveTerm(term) => {
+                term.chars().any(char::is_uppercase)
+            }
+            Query::Not(query) | Query::File(query) => query.has_uppercase_term(),
+            Query::And(left, right) | Query::Or(left, right) => {
+                left.has_uppercase_term() || right.has_uppercase_term()
+            }
+            Query::Constant(_) => false,
+        }
+    }
+
+    /// Terms that count towards a match when present in a line, with whether they ignore case, used to
+    /// report where a line matched
+    fn positive_terms<'a>(&'a self, negated: bool, terms: &mut Vec<(&'a str, bool)>) {
+        match self {
+            Query::Term(term) if !negated => terms.push((term, false)),
+            Query::CaseInsensitiveTerm(term) if !negated => terms.push((term, true)),
+            Query::Not(query) => query.positive_terms(!negated, terms),
+            Query::And(left, right) | Query::Or(left, right) => {
+                left.positive_terms(negated, terms);
+                right.positive_terms(negated, terms);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Matcher for Query {
+    /// Matching lines report the leftmost positive term they contain, or the whole line if there is none
+    fn find(&self, line: &str) -> Option<Match> {
+        if !self.matches_line(line) {
+            return None;
+        }
+        let mut terms: Vec<(&str, bool)> = Vec::new();
+        self.positive_terms(false, &mut terms);
+        terms
+            .iter()
+            .filter_map(|&(term, case_insensitive)| match case_insensitive {
+                true => find_case_insensitive(line, term),
+                false => line
+                    .find(term)
+                    .map(|start| Match::exact(start, start + term.len())),
+            })
+            .min_by_key(|found| found.start)
+            .or(Some(Match::exact(0, line.len())))
+    }
+
+    fn bind(&self, content: &str) -> Option<Box<dyn Matcher + '_>> {
+        Some(Box::new(self.bind_file(content)))
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<&Token> {
+        let token: Option<&Token> = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut query: Query = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut query: Query = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            query = Query::And(Box::new(query), Box::new(self.parse_not()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_not(&mut self) -> Result<Query, QueryError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.parse_not()?))),
+            Some(Token::Word(word)) => Ok(Query::Term(word.clone())),
+            Some(Token::Open) => {
+                let query: Query = self.parse_or()?;
+                self.expect_close()?;
+                Ok(query)
+            }
+            Some(Token::File) => {
+                match self.next() {
+                    Some(Token::Open) => (),
+                    Some(token) => return Err(QueryError::UnexpectedToken(token.to_string())),
+                    None => return Err(QueryError::UnexpectedEnd),
+                }
+                let query: Query = self.parse_or()?;
+                self.expect_close()?;
+                Ok(Query::File(Box::new(query)))
+            }
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), QueryError> {
+        match self.next() {
+            Some(Token::Close) => Ok(()),
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+}
+
+/// Split a query into tokens; keywords are only recognized in upper case, so `and` is a plain term
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let mut word: String = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => word.push(escaped),
+                            None => return Err(QueryError::UnterminatedString),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(QueryError::UnterminatedString),
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let mut word: String = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    "FILE" => Token::File,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
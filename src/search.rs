@@ -0,0 +1,140 @@
+//! The search engine tying a `Matcher` to a `Sink`.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use crate::index::Index;
+use crate::matcher::Matcher;
+use crate::sink::Sink;
+use crate::stats::{CountingReader, Stats};
+use crate::types::FileTypes;
+use crate::walk::{walk_files, warn_skipped};
+
+/// Runs a matcher over inputs line by line and reports matches and their context to a sink
+#[derive(Debug, Default, Clone)]
+pub struct Searcher {
+    /// Number of lines to report before each match
+    pub before_context: usize,
+    /// Number of lines to report after each match
+    pub after_context: usize,
+}
+
+impl Searcher {
+    /// Search a string, letting the matcher bind to the whole content first
+    pub fn search_str(
+        &self,
+        matcher: &impl Matcher,
+        path: Option<&Path>,
+        content: &str,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        match matcher.bind(content) {
+            Some(bound) => self.search_reader(&bound, path, content.as_bytes(), sink),
+            None => self.search_reader(matcher, path, content.as_bytes(), sink),
+        }
+    }
+
+    /// Search a reader without loading it into memory. Fails with `InvalidData` on lines that aren't UTF-8.
+    pub fn search_reader(
+        &self,
+        matcher: &impl Matcher,
+        path: Option<&Path>,
+        reader: impl BufRead,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        let mut reader = CountingReader::new(reader);
+        let mut lines_matched: u64 = 0;
+        let mut before: VecDeque<(usize, String)> = VecDeque::new();
+        let mut after_remaining: usize = 0;
+        let mut last_reported: Option<usize> = None;
+        let has_context: bool = self.before_context > 0 || self.after_context > 0;
+
+        sink.begin(path)?;
+        for (index, line) in (&mut reader).lines().enumerate() {
+            let line: String = line?;
+            let line_number: usize = index + 1;
+
+            if let Some(found) = matcher.find(&line) {
+                lines_matched += 1;
+                let first: usize = before.front().map_or(line_number, |(number, _)| *number);
+                if has_context && last_reported.is_some_and(|last| first > last + 1) {
+                    sink.context_break()?;
+                }
+                for (number, context) in before.drain(..) {
+                    sink.context(number, &context)?;
+                }
+                sink.matched(line_number, &line, found)?;
+                last_reported = Some(line_number);
+                after_remaining = self.after_context;
+            } else if after_remaining > 0 {
+                sink.context(line_number, &line)?;
+                last_reported = Some(line_number);
+                after_remaining -= 1;
+            } else if self.before_context > 0 {
+                if before.len() == self.before_context {
+                    before.pop_front();
+                }
+                before.push_back((line_number, line));
+            }
+        }
+        sink.end(path)?;
+        Ok(Stats::for_input(lines_matched, reader.count()))
+    }
+
+    /// Search every file below a directory, or only those of the selected file types when given.
+    /// Files that aren't valid UTF-8 are skipped. When an index is given and the matcher has a required
+    /// literal, files the index rules out aren't read.
+    pub fn search_dir(
+        &self,
+        matcher: &impl Matcher,
+        root: &Path,
+        index: Option<&Index>,
+        types: Option<&FileTypes>,
+        sink: &mut impl Sink,
+    ) -> io::Result<Stats> {
+        for_each_file(
+            root,
+            index,
+            types,
+            matcher.required_literal(),
+            |path, content| self.search_str(matcher, Some(path), content, sink),
+        )
+    }
+}
+
+/// Call `search` with the path and content of every file below a directory that could contain
+/// `literal`, adding up the stats it returns. Files that aren't valid UTF-8 are skipped, and so are
+/// files the index rules out and files that aren't of the selected types. Files that can't be read
+/// are skipped with a warning.
+pub fn for_each_file(
+    root: &Path,
+    index: Option<&Index>,
+    types: Option<&FileTypes>,
+    literal: Option<&str>,
+    mut search: impl FnMut(&Path, &str) -> io::Result<Stats>,
+) -> io::Result<Stats> {
+    let mut stats: Stats = Stats::default();
+    for path in walk_files(root)? {
+        if types.is_some_and(|types| !types.matches(&path)) {
+            continue;
+        }
+        if let (Some(index), Some(literal)) = (index, literal) {
+            if !index.may_contain(root, &path, literal) {
+                continue;
+            }
+        }
+
+        let content: String = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => continue,
+            Err(error) => {
+                warn_skipped(Some(&path), &error);
+                continue;
+            }
+        };
+        stats += search(&path, &content)?;
+    }
+    Ok(stats)
+}
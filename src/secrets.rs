@@ -0,0 +1,315 @@
+//! Secret scanning with a built-in pack of named rules, used by `grrs secrets`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::matcher::{Matcher, MultiLiteralMatcher};
+use crate::search::for_each_file;
+use crate::stats::Stats;
+
+/// Lines containing this marker are never reported, for known test fixtures and examples
+pub const ALLOW_MARKER: &str = "grrs:allow";
+
+/// A named pattern for one kind of secret
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// Matches the secret, with the secret itself in the first capture group when there is one
+    pub pattern: Regex,
+    /// Lowercase strings one of which every match contains, used to skip lines cheaply
+    pub keywords: &'static [&'static str],
+    /// Minimum Shannon entropy, in bits per character, the secret needs to be reported
+    pub min_entropy: Option<f64>,
+}
+
+impl Rule {
+    fn new(
+        id: &'static str,
+        description: &'static str,
+        pattern: &str,
+        keywords: &'static [&'static str],
+        min_entropy: Option<f64>,
+    ) -> Rule {
+        Rule {
+            id,
+            description,
+            pattern: Regex::new(pattern).expect("built-in rule patterns are valid"),
+            keywords,
+            min_entropy,
+        }
+    }
+}
+
+/// The built-in rule pack, most specific rules first so they win over generic ones for the same secret
+pub fn builtin_rules() -> Vec<Rule> {
+    vec![
+        Rule::new(
+            "aws-access-key-id",
+            "AWS access key ID",
+            r"\b((?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16})\b",
+            &["akia", "asia", "abia", "acca"],
+            None,
+        ),
+        Rule::new(
+            "aws-secret-access-key",
+            "AWS secret access key",
+            r#"(?i)aws_?secret_?access_?key["']?\s*[:=]\s*["']?([A-Za-z0-9/+]{40})\b"#,
+            &["aws"],
+            Some(4.0),
+        ),
+        Rule::new(
+            "private-key",
+            "Private key header",
+            r"-----BEGIN[ A-Z0-9_-]*PRIVATE KEY(?: BLOCK)?-----",
+            &["private key"],
+            None,
+        ),
+        Rule::new(
+            "jwt",
+            "JSON Web Token",
+            r"\b(eyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,})",
+            &["eyj"],
+            None,
+        ),
+        Rule::new(
+            "github-token",
+            "GitHub token",
+            r"\b(gh[pousr]_[A-Za-z0-9]{36})\b",
+            &["ghp_", "gho_", "ghu_", "ghs_", "ghr_"],
+            None,
+        ),
+        Rule::new(
+            "slack-token",
+            "Slack token",
+            r"\b(xox[baprs]-[A-Za-z0-9-]{10,})",
+            &["xox"],
+            None,
+        ),
+        Rule::new(
+            "generic-secret",
+            "Value assigned to a secret, token, password or API key",
+            r#"(?i)(?:secret|token|password|passwd|api_?key)[\w.-]*["']?\s*[:=]\s*["']([^"'\s]{8,})["']"#,
+            &["secret", "token", "password", "passwd", "api"],
+            Some(3.0),
+        ),
+        Rule::new(
+            "high-entropy-string",
+            "Quoted string with high entropy",
+            r#"["']([A-Za-z0-9+/=_-]{20,})["']"#,
+            &["\"", "'"],
+            Some(4.0),
+        ),
+    ]
+}
+
+/// Shannon entropy of a string in bits per character
+pub fn entropy(text: &str) -> f64 {
+    let mut counts: [usize; 256] = [0; 256];
+    for byte in text.bytes() {
+        counts[byte as usize] += 1;
+    }
+    let length: f64 = text.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let probability: f64 = count as f64 / length;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+/// A secret found by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule_id: String,
+    pub path: PathBuf,
+    pub line_number: usize,
+    /// Column of the first byte of the secret, starting at 1
+    pub column: usize,
+    /// The secret with everything but its first four characters masked
+    pub redacted: String,
+    pub entropy: f64,
+    /// Stable hash of the rule, the path relative to the scanned directory and the secret. Doesn't
+    /// change when the secret moves to another line, so baselines survive unrelated edits.
+    pub fingerprint: String,
+}
+
+/// Scans files with a set of rules, dropping allowed and already known findings
+pub struct SecretScanner {
+    pub rules: Vec<Rule>,
+    /// Findings whose secret or path matches one of these are ignored
+    pub allow: Vec<Regex>,
+    /// Replaces the entropy threshold of every rule that has one
+    pub min_entropy: Option<f64>,
+    /// Fingerprints of known findings, which aren't reported again
+    pub baseline: HashSet<String>,
+    prefilter: MultiLiteralMatcher,
+}
+
+impl SecretScanner {
+    pub fn new(rules: Vec<Rule>) -> SecretScanner {
+        let keywords: Vec<&str> = rules
+            .iter()
+            .flat_map(|rule| rule.keywords.iter().copied())
+            .collect();
+        SecretScanner {
+            prefilter: MultiLiteralMatcher::new(&keywords),
+            rules,
+            allow: Vec::new(),
+            min_entropy: None,
+            baseline: HashSet::new(),
+        }
+    }
+
+    /// Scan a string. `relative` is the path used for fingerprints, which should be the same between
+    /// runs even if the scanned directory is given differently.
+    pub fn scan_str(&self, path: &Path, relative: &Path, content: &str) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.contains(ALLOW_MARKER) || !self.prefilter.is_match(&line.to_lowercase()) {
+                continue;
+            }
+            // Spans already reported on this line, so generic rules don't repeat specific ones
+            let mut reported: Vec<(usize, usize)> = Vec::new();
+
+            for rule in &self.rules {
+                for captures in rule.pattern.captures_iter(line) {
+                    let secret = captures.get(1).or_else(|| captures.get(0)).unwrap();
+                    let (start, end): (usize, usize) = (secret.start(), secret.end());
+                    if reported.iter().any(|&(s, e)| start < e && s < end) {
+                        continue;
+                    }
+
+                    let entropy: f64 = entropy(secret.as_str());
+                    let threshold: Option<f64> =
+                        rule.min_entropy.map(|t| self.min_entropy.unwrap_or(t));
+                    if threshold.is_some_and(|threshold| entropy < threshold) {
+                        continue;
+                    }
+                    // Allowed and known secrets still claim their span
+                    reported.push((start, end));
+                    let path_text: String = path.display().to_string();
+                    if self
+                        .allow
+                        .iter()
+                        .any(|allow| allow.is_match(secret.as_str()) || allow.is_match(&path_text))
+                    {
+                        continue;
+                    }
+                    let fingerprint: String = fingerprint(rule.id, relative, secret.as_str());
+                    if self.baseline.contains(&fingerprint) {
+                        continue;
+                    }
+
+                    findings.push(Finding {
+                        rule_id: rule.id.to_string(),
+                        path: path.to_path_buf(),
+                        line_number: index + 1,
+                        column: start + 1,
+                        redacted: redact(secret.as_str()),
+                        entropy,
+                        fingerprint,
+                    });
+                }
+            }
+        }
+        findings.sort_by_key(|finding| (finding.line_number, finding.column));
+        findings
+    }
+
+    /// Scan a file, or every file below a directory. Files that aren't valid UTF-8 are skipped.
+    pub fn scan_path(&self, root: &Path) -> io::Result<(Vec<Finding>, Stats)> {
+        let mut findings: Vec<Finding> = Vec::new();
+        if !root.is_dir() {
+            let content: String = fs::read_to_string(root)?;
+            let relative: &Path = root.file_name().map_or(root, Path::new);
+            findings = self.scan_str(root, relative, &content);
+            let stats = Stats::for_input(findings.len() as u64, content.len() as u64);
+            return Ok((findings, stats));
+        }
+
+        let stats: Stats = for_each_file(root, None, None, None, |path, content| {
+            let relative: &Path = path.strip_prefix(root).unwrap_or(path);
+            let found: Vec<Finding> = self.scan_str(path, relative, content);
+            let stats = Stats::for_input(found.len() as u64, content.len() as u64);
+            findings.extend(found);
+            Ok(stats)
+        })?;
+        Ok((findings, stats))
+    }
+}
+
+/// Read the fingerprints from a baseline file, in the JSON lines format printed by
+/// `grrs secrets --format json`
+pub fn load_baseline(path: &Path) -> io::Result<HashSet<String>> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str::<Finding>(line)
+                .map(|finding| finding.fingerprint)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        })
+        .collect()
+}
+
+/// A SARIF 2.1.0 log of the findings, for code scanning services
+pub fn to_sarif(rules: &[Rule], findings: &[Finding]) -> Value {
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|rule| json!({ "id": rule.id, "shortDescription": { "text": rule.description } }))
+        .collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "ruleId": finding.rule_id,
+                "level": "error",
+                "message": { "text": format!("{} found: {}", finding.rule_id, finding.redacted) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.path.to_string_lossy().replace('\\', "/") },
+                        "region": { "startLine": finding.line_number, "startColumn": finding.column }
+                    }
+                }],
+                "partialFingerprints": { "grrs/v1": finding.fingerprint }
+            })
+        })
+        .collect();
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": { "driver": { "name": "grrs", "rules": rules } },
+            "results": results
+        }]
+    })
+}
+
+/// Keep the first four characters of a secret and mask the rest
+fn redact(secret: &str) -> String {
+    let shown: String = secret.chars().take(4).collect();
+    let hidden: usize = secret.chars().count() - shown.chars().count();
+    format!("{}{}", shown, "*".repeat(hidden))
+}
+
+/// FNV-1a hash of the rule, path and secret, which unlike the standard library's hasher is stable
+/// between releases
+fn fingerprint(rule_id: &str, path: &Path, secret: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let path: String = path.to_string_lossy().replace('\\', "/");
+    for part in [rule_id, &path, secret] {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,474 @@
+//! Receivers for the events produced while searching.
+
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::matcher::Match;
+use crate::stats::Stats;
+
+/// Receives the results of a search as a sequence of events.
+/// `begin` and `end` bracket each searched input; in between come matching lines, the context lines
+/// around them and breaks between non-adjacent groups of lines.
+pub trait Sink {
+    /// Called before an input is searched, with its path if it came from a file
+    fn begin(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called for every matching line, with its 1-based line number and the first match in it
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()>;
+
+    /// Called for every line printed as context around a match
+    fn context(&mut self, _line_number: usize, _line: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called between groups of lines that aren't adjacent in the input
+    fn context_break(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called after an input has been searched
+    fn end(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called to write out what has been reported so far while the input is still being read, such as
+    /// between polls in follow mode
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once at the end of a search, when stats were asked for
+    fn summary(&mut self, _stats: &Stats) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        (**self).begin(path)
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()> {
+        (**self).matched(line_number, line, found)
+    }
+
+    fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+        (**self).context(line_number, line)
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        (**self).context_break()
+    }
+
+    fn end(&mut self, path: Option<&Path>) -> io::Result<()> {
+        (**self).end(path)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn summary(&mut self, stats: &Stats) -> io::Result<()> {
+        (**self).summary(stats)
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        (**self).begin(path)
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()> {
+        (**self).matched(line_number, line, found)
+    }
+
+    fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+        (**self).context(line_number, line)
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        (**self).context_break()
+    }
+
+    fn end(&mut self, path: Option<&Path>) -> io::Result<()> {
+        (**self).end(path)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn summary(&mut self, stats: &Stats) -> io::Result<()> {
+        (**self).summary(stats)
+    }
+}
+
+/// Writes matching lines as plain text, prefixed by their path when searching files in a directory.
+/// Context lines use `-` instead of `:` after the path and groups are separated by `--`.
+pub struct StandardSink<W: Write> {
+    writer: W,
+    path: Option<PathBuf>,
+    with_path: bool,
+    heading: bool,
+    null: bool,
+    printed_heading: bool,
+    printed_any: bool,
+}
+
+impl<W: Write> StandardSink<W> {
+    pub fn new(writer: W) -> StandardSink<W> {
+        StandardSink {
+            writer,
+            path: None,
+            with_path: false,
+            heading: false,
+            null: false,
+            printed_heading: false,
+            printed_any: false,
+        }
+    }
+
+    /// Prefix every line with the path of the file it came from
+    pub fn with_path(mut self, with_path: bool) -> StandardSink<W> {
+        self.with_path = with_path;
+        self
+    }
+
+    /// Print the path once above the lines of each file instead of on every line, with a blank line
+    /// between files. Only has an effect together with `with_path`.
+    pub fn with_heading(mut self, heading: bool) -> StandardSink<W> {
+        self.heading = heading;
+        self
+    }
+
+    /// Follow paths with a NUL byte instead of a separator, so they can contain any character
+    pub fn with_null(mut self, null: bool) -> StandardSink<W> {
+        self.null = null;
+        self
+    }
+
+    fn write_line(&mut self, separator: char, line: &str) -> io::Result<()> {
+        let path: &Path = match (&self.path, self.with_path) {
+            (Some(path), true) => path,
+            _ => return writeln!(self.writer, "{}", line),
+        };
+
+        if self.heading {
+            if !self.printed_heading {
+                if self.printed_any {
+                    writeln!(self.writer)?;
+                }
+                write_path(&mut self.writer, path)?;
+                match self.null {
+                    true => self.writer.write_all(b"\0")?,
+                    false => writeln!(self.writer)?,
+                }
+                self.printed_heading = true;
+            }
+        } else {
+            write_path(&mut self.writer, path)?;
+            match self.null {
+                true => self.writer.write_all(b"\0")?,
+                false => write!(self.writer, "{}", separator)?,
+            }
+        }
+        self.printed_any = true;
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+impl<W: Write> Sink for StandardSink<W> {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.path = path.map(Path::to_path_buf);
+        self.printed_heading = false;
+        Ok(())
+    }
+
+    fn matched(&mut self, _line_number: usize, line: &str, _found: Match) -> io::Result<()> {
+        self.write_line(':', line)
+    }
+
+    fn context(&mut self, _line_number: usize, line: &str) -> io::Result<()> {
+        self.write_line('-', line)
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        writeln!(self.writer, "--")
+    }
+
+    fn end(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the path of each input containing at least one match, once
+pub struct FilesWithMatchesSink<W: Write> {
+    writer: W,
+    path: Option<PathBuf>,
+    null: bool,
+}
+
+impl<W: Write> FilesWithMatchesSink<W> {
+    pub fn new(writer: W) -> FilesWithMatchesSink<W> {
+        FilesWithMatchesSink {
+            writer,
+            path: None,
+            null: false,
+        }
+    }
+
+    /// End each path with a NUL byte instead of a newline, for `xargs -0`
+    pub fn with_null(mut self, null: bool) -> FilesWithMatchesSink<W> {
+        self.null = null;
+        self
+    }
+}
+
+impl<W: Write> Sink for FilesWithMatchesSink<W> {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.path = path.map(Path::to_path_buf);
+        Ok(())
+    }
+
+    fn matched(&mut self, _line_number: usize, _line: &str, _found: Match) -> io::Result<()> {
+        let path: PathBuf = match self.path.take() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        write_path(&mut self.writer, &path)?;
+        match self.null {
+            true => self.writer.write_all(b"\0"),
+            false => writeln!(self.writer),
+        }
+    }
+
+    fn end(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes one line per matching line in the `path:line:column:text` format understood by editors such
+/// as vim's `:grep`. Columns are 1-based byte offsets of the first match in the line.
+pub struct VimgrepSink<W: Write> {
+    writer: W,
+    path: Option<PathBuf>,
+    null: bool,
+}
+
+impl<W: Write> VimgrepSink<W> {
+    pub fn new(writer: W) -> VimgrepSink<W> {
+        VimgrepSink {
+            writer,
+            path: None,
+            null: false,
+        }
+    }
+
+    /// Follow paths with a NUL byte instead of `:`, so they can contain any character
+    pub fn with_null(mut self, null: bool) -> VimgrepSink<W> {
+        self.null = null;
+        self
+    }
+}
+
+impl<W: Write> Sink for VimgrepSink<W> {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.path = path.map(Path::to_path_buf);
+        Ok(())
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            write_path(&mut self.writer, path)?;
+            match self.null {
+                true => self.writer.write_all(b"\0")?,
+                false => write!(self.writer, ":")?,
+            }
+        }
+        writeln!(self.writer, "{}:{}:{}", line_number, found.start + 1, line)
+    }
+
+    fn end(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Matches found in one input, as gathered by `CollectingSink`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMatches {
+    pub path: Option<PathBuf>,
+    /// Line number, line and first match of every matching line
+    pub matches: Vec<(usize, String, Match)>,
+}
+
+/// Keeps matches in memory, grouped by input, for callers that present results themselves.
+/// Inputs without any matches are left out.
+#[derive(Debug, Default)]
+pub struct CollectingSink {
+    pub files: Vec<FileMatches>,
+}
+
+impl CollectingSink {
+    pub fn new() -> CollectingSink {
+        CollectingSink::default()
+    }
+}
+
+impl Sink for CollectingSink {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.files.push(FileMatches {
+            path: path.map(Path::to_path_buf),
+            matches: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()> {
+        if self.files.is_empty() {
+            self.begin(None)?;
+        }
+        if let Some(file) = self.files.last_mut() {
+            file.matches.push((line_number, line.to_string(), found));
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, _path: Option<&Path>) -> io::Result<()> {
+        if self
+            .files
+            .last()
+            .is_some_and(|file| file.matches.is_empty())
+        {
+            self.files.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Write a path exactly as the file system stores it where possible, so it round-trips through pipes
+#[cfg(unix)]
+fn write_path(mut writer: impl Write, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    writer.write_all(path.as_os_str().as_bytes())
+}
+
+/// Write a path exactly as the file system stores it where possible, so it round-trips through pipes
+#[cfg(not(unix))]
+fn write_path(mut writer: impl Write, path: &Path) -> io::Result<()> {
+    write!(writer, "{}", path.display())
+}
+
+/// A single line of `JsonSink` output
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonEvent<'a> {
+    Begin {
+        path: Option<String>,
+    },
+    Match {
+        path: Option<String>,
+        line_number: usize,
+        line: &'a str,
+        start: usize,
+        end: usize,
+        distance: usize,
+    },
+    Context {
+        path: Option<String>,
+        line_number: usize,
+        line: &'a str,
+    },
+    End {
+        path: Option<String>,
+    },
+    Summary {
+        files_searched: u64,
+        files_matched: u64,
+        lines_matched: u64,
+        bytes_searched: u64,
+        elapsed_secs: f64,
+    },
+}
+
+/// Writes every event as a JSON object on its own line, for consumption by other tools.
+/// Matches carry their byte offsets and edit distance, which is 0 unless fuzzy matching is used.
+pub struct JsonSink<W: Write> {
+    writer: W,
+    path: Option<String>,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W) -> JsonSink<W> {
+        JsonSink { writer, path: None }
+    }
+
+    fn write_event(&mut self, event: &JsonEvent) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> Sink for JsonSink<W> {
+    fn begin(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.path = path.map(|path| path.display().to_string());
+        self.write_event(&JsonEvent::Begin {
+            path: self.path.clone(),
+        })
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, found: Match) -> io::Result<()> {
+        self.write_event(&JsonEvent::Match {
+            path: self.path.clone(),
+            line_number,
+            line,
+            start: found.start,
+            end: found.end,
+            distance: found.distance,
+        })
+    }
+
+    fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+        self.write_event(&JsonEvent::Context {
+            path: self.path.clone(),
+            line_number,
+            line,
+        })
+    }
+
+    fn end(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.write_event(&JsonEvent::End {
+            path: path.map(|path| path.display().to_string()),
+        })?;
+        self.writer.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn summary(&mut self, stats: &Stats) -> io::Result<()> {
+        self.write_event(&JsonEvent::Summary {
+            files_searched: stats.files_searched,
+            files_matched: stats.files_matched,
+            lines_matched: stats.lines_matched,
+            bytes_searched: stats.bytes_searched,
+            elapsed_secs: stats.elapsed.as_secs_f64(),
+        })?;
+        self.writer.flush()
+    }
+}
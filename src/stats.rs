@@ -0,0 +1,75 @@
+//! Counters describing how much work a search did.
+
+use std::io::{self, BufRead, Read};
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Totals for a search, returned by every searcher and summed over the files of a directory
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub files_searched: u64,
+    pub files_matched: u64,
+    pub lines_matched: u64,
+    pub bytes_searched: u64,
+    /// Wall clock time of the whole search, filled in by the caller that timed it
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    /// Stats for a single searched input
+    pub fn for_input(lines_matched: u64, bytes_searched: u64) -> Stats {
+        Stats {
+            files_searched: 1,
+            files_matched: (lines_matched > 0) as u64,
+            lines_matched,
+            bytes_searched,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Stats) {
+        self.files_searched += other.files_searched;
+        self.files_matched += other.files_matched;
+        self.lines_matched += other.lines_matched;
+        self.bytes_searched += other.bytes_searched;
+        self.elapsed += other.elapsed;
+    }
+}
+
+/// Wraps a reader and counts the bytes read through it
+pub struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R> CountingReader<R> {
+    pub fn new(inner: R) -> CountingReader<R> {
+        CountingReader { inner, count: 0 }
+    }
+
+    /// Number of bytes read so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read: usize = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt)
+    }
+}
@@ -0,0 +1,151 @@
+//! Named file types, defined by globs on file names, for restricting directory searches with `--type`.
+//!
+//! A handful of common types are built in, and more can be defined or extended with definitions like
+//! `proto:*.proto`, typically from the config file.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Globs of the built-in types
+const BUILTIN_TYPES: &[(&str, &[&str])] = &[
+    ("c", &["*.c", "*.h"]),
+    ("cpp", &["*.cpp", "*.cc", "*.cxx", "*.hpp", "*.hh", "*.hxx"]),
+    ("csv", &["*.csv", "*.tsv"]),
+    ("go", &["*.go"]),
+    ("html", &["*.html", "*.htm"]),
+    ("java", &["*.java"]),
+    ("js", &["*.js", "*.mjs", "*.cjs", "*.jsx"]),
+    ("json", &["*.json", "*.jsonl", "*.ndjson"]),
+    ("log", &["*.log"]),
+    ("md", &["*.md", "*.markdown"]),
+    ("py", &["*.py", "*.pyi"]),
+    ("rust", &["*.rs"]),
+    ("sh", &["*.sh", "*.bash", "*.zsh"]),
+    ("toml", &["*.toml"]),
+    ("ts", &["*.ts", "*.tsx"]),
+    ("txt", &["*.txt"]),
+    ("yaml", &["*.yaml", "*.yml"]),
+];
+
+/// Errors from defining or selecting file types
+#[derive(Debug, PartialEq)]
+pub enum TypeError {
+    /// A definition that isn't of the form `NAME:GLOB`
+    InvalidDefinition(String),
+    UnknownType(String),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::InvalidDefinition(definition) => write!(
+                f,
+                "invalid file type definition `{}`, expected NAME:GLOB",
+                definition
+            ),
+            TypeError::UnknownType(name) => write!(f, "unknown file type `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// File type definitions and the types selected from them. With nothing selected, every file matches.
+#[derive(Debug, Clone)]
+pub struct FileTypes {
+    definitions: BTreeMap<String, Vec<String>>,
+    selected: Vec<String>,
+}
+
+impl Default for FileTypes {
+    fn default() -> FileTypes {
+        FileTypes::new()
+    }
+}
+
+impl FileTypes {
+    /// The built-in types, with none selected
+    pub fn new() -> FileTypes {
+        let definitions: BTreeMap<String, Vec<String>> = BUILTIN_TYPES
+            .iter()
+            .map(|(name, globs)| {
+                let globs: Vec<String> = globs.iter().map(|glob| glob.to_string()).collect();
+                (name.to_string(), globs)
+            })
+            .collect();
+        FileTypes {
+            definitions,
+            selected: Vec::new(),
+        }
+    }
+
+    /// Add a glob to a type from a definition like `proto:*.proto`, creating the type if it doesn't exist.
+    /// Globs match file names, with `*` standing for any run of characters and `?` for one character.
+    pub fn add(&mut self, definition: &str) -> Result<(), TypeError> {
+        let (name, glob) = match definition.split_once(':') {
+            Some((name, glob)) if !name.is_empty() && !glob.is_empty() => (name, glob),
+            _ => return Err(TypeError::InvalidDefinition(definition.to_string())),
+        };
+        self.definitions
+            .entry(name.to_string())
+            .or_default()
+            .push(glob.to_string());
+        Ok(())
+    }
+
+    /// Only match files of this type, in addition to any selected before
+    pub fn select(&mut self, name: &str) -> Result<(), TypeError> {
+        if !self.definitions.contains_key(name) {
+            return Err(TypeError::UnknownType(name.to_string()));
+        }
+        self.selected.push(name.to_string());
+        Ok(())
+    }
+
+    /// Whether a file is of one of the selected types, or any file when no type is selected
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.selected.is_empty() {
+            return true;
+        }
+        let name: String = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => return false,
+        };
+        self.selected
+            .iter()
+            .flat_map(|selected| &self.definitions[selected])
+            .any(|glob| glob_matches(glob, &name))
+    }
+}
+
+/// Match a file name against a glob with `*` and `?` wildcards
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n): (usize, usize) = (0, 0);
+    // Position of the last `*` and the name position it was tried at, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    g = star_g + 1;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,51 @@
+//! Recursive directory walking shared by directory searches and the index builder.
+
+use std::fs::{self, DirEntry};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::index::INDEX_FILE_NAME;
+
+/// Collect every file below a directory, sorted so output is stable between runs.
+/// Symlinks are not followed and the index file itself is left out. Entries below the directory that
+/// can't be read are skipped with a warning on stderr, like `grep -r` does, while an unreadable `root`
+/// is an error.
+pub fn walk_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(root)? {
+        walk_entry(entry, &mut files);
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn walk_entry(entry: io::Result<DirEntry>, files: &mut Vec<PathBuf>) {
+    let entry: DirEntry = match entry {
+        Ok(entry) => entry,
+        Err(error) => return warn_skipped(None, &error),
+    };
+    let file_type = match entry.file_type() {
+        Ok(file_type) => file_type,
+        Err(error) => return warn_skipped(Some(&entry.path()), &error),
+    };
+    if file_type.is_dir() {
+        match fs::read_dir(entry.path()) {
+            Ok(entries) => {
+                for entry in entries {
+                    walk_entry(entry, files);
+                }
+            }
+            Err(error) => warn_skipped(Some(&entry.path()), &error),
+        }
+    } else if file_type.is_file() && entry.file_name() != INDEX_FILE_NAME {
+        files.push(entry.path());
+    }
+}
+
+/// Tell the user an entry that couldn't be read was left out of a directory search
+pub(crate) fn warn_skipped(path: Option<&Path>, error: &io::Error) {
+    match path {
+        Some(path) => eprintln!("{}: {}", path.display(), error),
+        None => eprintln!("{}", error),
+    }
+}